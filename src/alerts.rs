@@ -0,0 +1,516 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::{self, AlertRule, Comparison, Config, Entities};
+use crate::state::{PowerData, Severity};
+
+/// Cleared alerts kept in ~/.emon/alert_log.json
+const LOG_SIZE: usize = 500;
+
+/// Cleared alerts listed after the active ones, for the panel and the Alerts page
+const HISTORY_SHOWN: usize = 30;
+
+/// A raised alert; `cleared_at` is set once the value is back past the hysteresis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub name: String,
+    pub metric: String,
+    pub severity: Severity,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    pub value: f64, // Latest value while active, the value at clearing afterwards
+    pub raised_at: String, // RFC 3339, local time
+    pub cleared_at: Option<String>,
+    // Terminal actions for the TUI to perform, false when the rule is rate-limited
+    #[serde(default)]
+    pub bell: bool,
+    #[serde(default)]
+    pub notify: bool,
+    #[serde(default)]
+    pub acknowledged: bool,
+    #[serde(default)]
+    pub snoozed_until: Option<String>, // RFC 3339; actions of the rule are silenced until then
+}
+
+impl Alert {
+    pub fn is_active(&self) -> bool {
+        self.cleared_at.is_none()
+    }
+
+    pub fn is_snoozed(&self) -> bool {
+        self.snoozed_until
+            .as_deref()
+            .and_then(|until| DateTime::parse_from_rfc3339(until).ok())
+            .is_some_and(|until| until > Utc::now())
+    }
+
+    /// Acknowledged or snoozed alerts no longer ask for attention
+    pub fn is_silenced(&self) -> bool {
+        self.acknowledged || self.is_snoozed()
+    }
+
+    fn same(&self, other: &Alert) -> bool {
+        self.name == other.name && self.raised_at == other.raised_at
+    }
+}
+
+/// An alert active at the time of a reading; each reading keeps only these, the full list
+/// lives on `AppState` and `Frame`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveAlert {
+    pub name: String,
+    pub metric: String,
+    pub severity: Severity,
+}
+
+#[derive(Debug)]
+struct RuleState {
+    rule: AlertRule,
+    pending_since: Option<Instant>, // Condition true but not held for `for` yet
+    active: Option<Alert>,
+    last_actions: Option<Instant>, // For `min_interval`
+    actions_fired: bool,           // Actions ran when the active alert was raised
+    snoozed_until: Option<DateTime<Tz>>,
+}
+
+impl RuleState {
+    /// Whether `alert` was raised by this rule
+    fn raised(&self, alert: &Alert) -> bool {
+        self.rule.label() == alert.name
+            && self.rule.metric == alert.metric
+            && self.rule.comparison == alert.comparison
+            && self.rule.threshold == alert.threshold
+    }
+}
+
+/// Alert log as persisted; alerts still active when it was saved are closed at `saved_at`
+#[derive(Debug, Serialize, Deserialize)]
+struct AlertLog {
+    saved_at: String,
+    alerts: Vec<Alert>,
+}
+
+/// Evaluates `[[alerts]]` rules against every reading and keeps the alert log
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: Vec<RuleState>,
+    log: VecDeque<Alert>, // Cleared alerts, most recent last
+    path: Option<PathBuf>,
+    dirty: bool,
+    hooks: bool, // Run rule commands; only in the process that owns polling
+}
+
+impl AlertEngine {
+    /// `owner` runs rule commands and writes the alert log; other processes only read the log
+    pub fn new(config: &Config, owner: bool) -> Self {
+        let rules = match &config.alerts {
+            Some(rules) => rules.clone(),
+            None => default_rules(&config.home_assistant.entities),
+        };
+
+        let path = config::get_config_dir()
+            .ok()
+            .map(|dir| dir.join("alert_log.json"));
+        let saved: Option<AlertLog> = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok());
+        let log = saved
+            .map(|saved| {
+                let saved_at = saved.saved_at;
+                saved
+                    .alerts
+                    .into_iter()
+                    .map(|mut alert| {
+                        alert.cleared_at.get_or_insert_with(|| saved_at.clone());
+                        alert
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            rules: rules
+                .into_iter()
+                .map(|rule| RuleState {
+                    rule,
+                    pending_since: None,
+                    active: None,
+                    last_actions: None,
+                    actions_fired: false,
+                    snoozed_until: None,
+                })
+                .collect(),
+            log,
+            path: path.filter(|_| owner),
+            dirty: false,
+            hooks: owner,
+        }
+    }
+
+    /// Raise and clear alerts from the reading and record the active ones on it
+    pub fn apply(&mut self, data: &mut PowerData, now: DateTime<Tz>) {
+        let fields = data.numeric_fields();
+        let timestamp = now.to_rfc3339();
+
+        for state in &mut self.rules {
+            if state.snoozed_until.is_some_and(|until| until <= now) {
+                state.snoozed_until = None;
+                if let Some(alert) = state.active.as_mut() {
+                    alert.snoozed_until = None;
+                }
+            }
+
+            let rule = &state.rule;
+            // Metrics that are missing from this reading keep their current state
+            let Some(value) = fields
+                .iter()
+                .find(|(name, _)| *name == rule.metric)
+                .map(|(_, value)| *value)
+            else {
+                continue;
+            };
+
+            match &mut state.active {
+                Some(alert) => {
+                    alert.value = value;
+                    if clears(rule, value) {
+                        let mut alert = state.active.take().expect("alert is active");
+                        alert.cleared_at = Some(timestamp.clone());
+                        let silenced = alert.acknowledged || state.snoozed_until.is_some();
+                        if state.actions_fired && !silenced && self.hooks {
+                            if let Some(command) = &rule.command {
+                                run_command(command, "cleared", &alert);
+                            }
+                        }
+                        self.log.push_back(alert);
+                        while self.log.len() > LOG_SIZE {
+                            self.log.pop_front();
+                        }
+                        self.dirty = true;
+                    }
+                }
+                None if matches(rule.comparison, value, rule.threshold) => {
+                    let since = *state.pending_since.get_or_insert(data.timestamp);
+                    if data.timestamp.saturating_duration_since(since) >= rule.get_duration() {
+                        state.pending_since = None;
+                        // Rate limit so a flapping sensor doesn't spam
+                        let has_actions = rule.command.is_some() || rule.bell || rule.notify;
+                        let allowed = state.last_actions.is_none_or(|at| {
+                            data.timestamp.saturating_duration_since(at) >= rule.get_min_interval()
+                        });
+                        state.actions_fired =
+                            has_actions && allowed && state.snoozed_until.is_none();
+                        if state.actions_fired {
+                            state.last_actions = Some(data.timestamp);
+                        }
+
+                        let alert = Alert {
+                            name: rule.label().to_string(),
+                            metric: rule.metric.clone(),
+                            severity: rule.severity,
+                            comparison: rule.comparison,
+                            threshold: rule.threshold,
+                            value,
+                            raised_at: timestamp.clone(),
+                            cleared_at: None,
+                            bell: state.actions_fired && rule.bell,
+                            notify: state.actions_fired && rule.notify,
+                            acknowledged: false,
+                            snoozed_until: state.snoozed_until.map(|until| until.to_rfc3339()),
+                        };
+                        if state.actions_fired && self.hooks {
+                            if let Some(command) = &rule.command {
+                                run_command(command, "raised", &alert);
+                            }
+                        }
+                        state.active = Some(alert);
+                        self.dirty = true;
+                    }
+                }
+                None => state.pending_since = None,
+            }
+        }
+
+        if self.dirty {
+            self.save();
+        }
+        data.active_alerts = self
+            .rules
+            .iter()
+            .filter_map(|state| state.active.as_ref())
+            .map(|alert| ActiveAlert {
+                name: alert.name.clone(),
+                metric: alert.metric.clone(),
+                severity: alert.severity,
+            })
+            .collect();
+    }
+
+    /// Active alerts, most severe first, followed by the most recently cleared ones
+    pub fn alerts(&self) -> Vec<Alert> {
+        let mut alerts: Vec<Alert> = self
+            .rules
+            .iter()
+            .filter_map(|state| state.active.clone())
+            .collect();
+        alerts.sort_by_key(|a| std::cmp::Reverse(a.severity));
+        alerts.extend(self.log.iter().rev().take(HISTORY_SHOWN).cloned());
+        alerts
+    }
+
+    /// Acknowledge an active alert, silencing its remaining actions
+    pub fn acknowledge(&mut self, alert: &Alert) {
+        for state in &mut self.rules {
+            if let Some(active) = state.active.as_mut().filter(|a| a.same(alert)) {
+                active.acknowledged = true;
+                self.dirty = true;
+            }
+        }
+        self.save();
+    }
+
+    /// Silence the actions of the rule that raised `alert` for `duration`; None lifts the snooze
+    pub fn snooze(&mut self, alert: &Alert, duration: Option<Duration>, now: DateTime<Tz>) {
+        for state in self.rules.iter_mut().filter(|s| s.raised(alert)) {
+            state.snoozed_until = duration
+                .and_then(|d| chrono::Duration::from_std(d).ok())
+                .map(|d| now + d);
+            if let Some(active) = state.active.as_mut() {
+                active.snoozed_until = state.snoozed_until.map(|until| until.to_rfc3339());
+            }
+        }
+        self.save();
+    }
+
+    fn save(&mut self) {
+        self.dirty = false;
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut alerts: Vec<Alert> = self.log.iter().cloned().collect();
+        alerts.extend(self.rules.iter().filter_map(|state| state.active.clone()));
+        let log = AlertLog {
+            saved_at: Utc::now().to_rfc3339(),
+            alerts,
+        };
+        let Ok(json) = serde_json::to_string_pretty(&log) else {
+            return;
+        };
+
+        // Write to a temporary file first so a crash never leaves a truncated log
+        let tmp = path.with_extension("json.tmp");
+        if fs::write(&tmp, json).is_ok() {
+            let _ = fs::rename(&tmp, path);
+        }
+    }
+}
+
+impl Drop for AlertEngine {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
+
+/// Tracks which alerts were active in the previous reading, to report changes once
+#[derive(Debug, Default)]
+pub struct AlertWatcher {
+    active: Vec<Alert>,
+}
+
+impl AlertWatcher {
+    /// Alerts raised and cleared since the previous call, given the current alert list
+    pub fn changes(&mut self, alerts: &[Alert]) -> (Vec<Alert>, Vec<Alert>) {
+        let same = |a: &Alert, b: &Alert| a.same(b);
+        let current: Vec<Alert> = alerts.iter().filter(|a| a.is_active()).cloned().collect();
+
+        let raised = current
+            .iter()
+            .filter(|a| !self.active.iter().any(|b| same(a, b)))
+            .cloned()
+            .collect();
+        let cleared = self
+            .active
+            .iter()
+            .filter(|a| !current.iter().any(|b| same(a, b)))
+            .map(|a| {
+                // Prefer the cleared copy, which has the value at clearing
+                alerts.iter().find(|b| same(a, b)).unwrap_or(a).clone()
+            })
+            .collect();
+        self.active = current;
+        (raised, cleared)
+    }
+}
+
+/// Run an alert's command in the background with the alert as JSON on stdin
+fn run_command(command: &str, event: &str, alert: &Alert) {
+    let mut payload = serde_json::to_value(alert).unwrap_or_default();
+    if let Some(object) = payload.as_object_mut() {
+        object.insert("event".to_string(), event.into());
+    }
+
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return;
+    };
+    // Reap the child off the fetch path
+    std::thread::spawn(move || {
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = writeln!(stdin, "{}", payload);
+        }
+        let _ = child.wait();
+    });
+}
+
+/// Escape sequences for a raised alert's terminal actions: BEL and OSC 9/777 notifications
+///
+/// Inside tmux the OSC sequences are wrapped for passthrough (needs `allow-passthrough on`).
+pub fn terminal_actions(alert: &Alert) -> String {
+    let mut out = String::new();
+    if alert.notify {
+        let title = format!("emon {:?}", alert.severity).to_uppercase();
+        let body = format!(
+            "{}: {:.1} {} {:.1}",
+            alert.name,
+            alert.value,
+            alert.comparison.symbol(),
+            alert.threshold
+        )
+        .replace(['\x07', '\x1b', ';'], " ");
+        for osc in [
+            format!("\x1b]9;{} - {}\x07", title, body),
+            format!("\x1b]777;notify;{};{}\x07", title, body),
+        ] {
+            if std::env::var_os("TMUX").is_some() {
+                out.push_str(&format!(
+                    "\x1bPtmux;{}\x1b\\",
+                    osc.replace('\x1b', "\x1b\x1b")
+                ));
+            } else {
+                out.push_str(&osc);
+            }
+        }
+    }
+    if alert.bell {
+        out.push('\x07');
+    }
+    out
+}
+
+fn matches(comparison: Comparison, value: f64, threshold: f64) -> bool {
+    match comparison {
+        Comparison::Above => value > threshold,
+        Comparison::AtLeast => value >= threshold,
+        Comparison::Below => value < threshold,
+        Comparison::AtMost => value <= threshold,
+    }
+}
+
+/// An active alert clears once the value is back past the threshold by the hysteresis
+fn clears(rule: &AlertRule, value: f64) -> bool {
+    let hysteresis = rule.hysteresis.abs();
+    match rule.comparison {
+        Comparison::Above | Comparison::AtLeast => value < rule.threshold - hysteresis,
+        Comparison::Below | Comparison::AtMost => value > rule.threshold + hysteresis,
+    }
+}
+
+/// Highest severity among active alerts on `metric`
+pub fn severity_of(data: &PowerData, metric: &str) -> Severity {
+    data.active_alerts
+        .iter()
+        .filter(|alert| alert.metric == metric)
+        .map(|alert| alert.severity)
+        .max()
+        .unwrap_or(Severity::Normal)
+}
+
+/// Built-in rules used without an `[[alerts]]` section, for configured sensors only
+fn default_rules(entities: &Entities) -> Vec<AlertRule> {
+    let rule = |metric: &str, comparison, threshold, hysteresis, severity| AlertRule {
+        name: None,
+        metric: metric.to_string(),
+        comparison,
+        threshold,
+        hysteresis,
+        duration: None,
+        severity,
+        command: None,
+        bell: false,
+        notify: false,
+        min_interval: None,
+    };
+
+    let mut rules = Vec::new();
+    // Temperatures only warn when high
+    for (metric, entity) in [
+        ("battery_temp", &entities.battery_temp),
+        ("inverter_temp", &entities.inverter_temp),
+        ("radiator_temp", &entities.radiator_temp),
+        ("dc_transformer_temp", &entities.dc_transformer_temp),
+    ] {
+        if entity.is_some() {
+            rules.push(rule(
+                metric,
+                Comparison::AtLeast,
+                35.0,
+                1.0,
+                Severity::Warning,
+            ));
+        }
+    }
+    // AC voltage warns below 220 V and is critical below 210 V
+    for (metric, entity) in [
+        ("inverter_voltage", &entities.inverter_voltage),
+        ("grid_voltage", &entities.grid_voltage),
+    ] {
+        if entity.is_some() {
+            rules.push(rule(
+                metric,
+                Comparison::Below,
+                220.0,
+                2.0,
+                Severity::Warning,
+            ));
+            rules.push(rule(
+                metric,
+                Comparison::Below,
+                210.0,
+                2.0,
+                Severity::Critical,
+            ));
+        }
+    }
+    // Current warns at 80 A and is critical at 100 A
+    if entities.load_current.is_some() {
+        rules.push(rule(
+            "load_current",
+            Comparison::AtLeast,
+            80.0,
+            2.0,
+            Severity::Warning,
+        ));
+        rules.push(rule(
+            "load_current",
+            Comparison::AtLeast,
+            100.0,
+            2.0,
+            Severity::Critical,
+        ));
+    }
+    rules
+}
@@ -0,0 +1,243 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::config::{self, HomeAssistantConfig, VoltageSoc};
+use crate::state::PowerData;
+
+/// Time constant of the exponential smoothing applied to battery power
+const SMOOTHING: Duration = Duration::from_secs(120);
+
+/// Below this the battery is treated as idle (same threshold as `BatteryState`)
+const IDLE_WATTS: f64 = 10.0;
+
+/// Gaps longer than this are not integrated by the SoC estimator
+const MAX_GAP: Duration = Duration::from_secs(300);
+
+/// Idle time before the voltage is trusted as a resting voltage
+const REST: Duration = Duration::from_secs(600);
+
+/// How often the estimated SoC is written to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A saved estimate older than this is discarded; the battery may have moved a lot meanwhile
+const MAX_RESTORE_AGE: Duration = Duration::from_secs(600);
+
+/// Smooths battery power and estimates time to full / time to reserve
+#[derive(Debug, Default)]
+pub struct BatteryEstimator {
+    smoothed: Option<(Instant, f64)>,
+}
+
+impl BatteryEstimator {
+    pub fn apply(&mut self, data: &mut PowerData, config: &HomeAssistantConfig) {
+        data.battery_time_to_full = None;
+        data.battery_time_to_reserve = None;
+
+        // A gap in battery power is skipped, not blended in as 0 W
+        let power = match data.unavailable.get("battery_power") {
+            Some(&reason) => {
+                data.unavailable
+                    .insert("battery_power_smoothed".to_string(), reason);
+                None
+            }
+            None => {
+                let power = match self.smoothed {
+                    Some((at, previous)) => {
+                        let dt = data.timestamp.saturating_duration_since(at).as_secs_f64();
+                        let alpha = 1.0 - (-dt / SMOOTHING.as_secs_f64()).exp();
+                        previous + alpha * (data.battery_power - previous)
+                    }
+                    None => data.battery_power,
+                };
+                self.smoothed = Some((data.timestamp, power));
+                data.battery_power_smoothed = power;
+                Some(power)
+            }
+        };
+
+        let Some(capacity_kwh) = config.battery_capacity_kwh else {
+            data.battery_stored_kwh = None;
+            return;
+        };
+        if !data.is_available("battery_soc") {
+            data.battery_stored_kwh = None;
+            return;
+        }
+
+        let soc = data.battery_soc.clamp(0.0, 100.0);
+        data.battery_stored_kwh = Some(soc / 100.0 * capacity_kwh);
+        let Some(power) = power else {
+            return;
+        };
+        let reserve = config.get_battery_reserve_soc();
+        let hours = |soc_delta: f64, watts: f64| soc_delta / 100.0 * capacity_kwh * 1000.0 / watts;
+
+        if power > IDLE_WATTS && soc < 100.0 {
+            data.battery_time_to_full = Some(hours(100.0 - soc, power) * 3600.0);
+        } else if power < -IDLE_WATTS && soc > reserve {
+            data.battery_time_to_reserve = Some(hours(soc - reserve, -power) * 3600.0);
+        }
+    }
+}
+
+/// Estimates SoC by coulomb counting when no `battery_soc` sensor is configured
+///
+/// Re-anchors to 100% at float voltage and, with a `battery_voltage_soc` curve,
+/// to the curve after the battery has rested.
+#[derive(Debug)]
+pub struct SocEstimator {
+    soc: Option<f64>,
+    last: Option<(Instant, f64)>, // Previous (time, battery power in W)
+    idle_since: Option<Instant>,
+    path: Option<PathBuf>,
+    last_save: Instant,
+}
+
+/// Estimated SoC as saved on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedSoc {
+    soc: f64,
+    saved_at: String, // RFC 3339
+}
+
+impl SocEstimator {
+    /// Without `persist` the saved estimate is read but never written back. A stale estimate
+    /// is dropped, so the voltage curve (or the next float) seeds a fresh one.
+    pub fn new(persist: bool) -> Self {
+        let path = config::get_config_dir()
+            .ok()
+            .map(|dir| dir.join("soc_estimate.json"));
+        let soc = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str::<SavedSoc>(&content).ok())
+            .filter(|saved| {
+                DateTime::parse_from_rfc3339(&saved.saved_at)
+                    .ok()
+                    .and_then(|at| (Utc::now() - at.with_timezone(&Utc)).to_std().ok())
+                    .is_some_and(|age| age <= MAX_RESTORE_AGE)
+            })
+            .map(|saved| saved.soc);
+
+        Self {
+            soc,
+            last: None,
+            idle_since: None,
+            path: path.filter(|_| persist),
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Replace `data.battery_soc` with the estimate; `floating` comes from `is_battery_floating`
+    pub fn apply(&mut self, data: &mut PowerData, config: &HomeAssistantConfig, floating: bool) {
+        let entities = &config.entities;
+        let Some(capacity_kwh) = config.battery_capacity_kwh else {
+            return;
+        };
+        if entities.battery_soc.is_some() {
+            return;
+        }
+        // A missing reading is not an idle battery
+        if !data.is_available("battery_power") || !data.is_available("battery_current") {
+            return;
+        }
+        let power = if entities.battery_power.is_some() {
+            data.battery_power
+        } else if entities.battery_current.is_some() && data.battery_voltage > 0.0 {
+            data.battery_current * data.battery_voltage
+        } else {
+            return;
+        };
+
+        if power.abs() < IDLE_WATTS {
+            self.idle_since.get_or_insert(data.timestamp);
+        } else {
+            self.idle_since = None;
+        }
+        let rested = self
+            .idle_since
+            .is_some_and(|since| data.timestamp.saturating_duration_since(since) >= REST);
+        let curve_soc = config
+            .battery_voltage_soc
+            .as_deref()
+            .filter(|_| data.battery_voltage > 0.0)
+            .map(|curve| soc_from_voltage(curve, data.battery_voltage));
+
+        self.soc = match (self.soc, self.last) {
+            _ if floating => Some(100.0),
+            _ if rested && curve_soc.is_some() => curve_soc,
+            (None, _) => curve_soc,
+            (Some(soc), Some((at, previous))) => {
+                let dt = data.timestamp.saturating_duration_since(at);
+                if dt <= MAX_GAP {
+                    let kwh = (previous + power) / 2.0 * dt.as_secs_f64() / 3600.0 / 1000.0;
+                    Some((soc + kwh / capacity_kwh * 100.0).clamp(0.0, 100.0))
+                } else {
+                    Some(soc)
+                }
+            }
+            (Some(soc), None) => Some(soc),
+        };
+        self.last = Some((data.timestamp, power));
+
+        if let Some(soc) = self.soc {
+            data.battery_soc = soc;
+            data.battery_soc_estimated = true;
+        }
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    fn save(&mut self) {
+        self.last_save = Instant::now();
+        let (Some(path), Some(soc)) = (&self.path, self.soc) else {
+            return;
+        };
+        let saved = SavedSoc {
+            soc,
+            saved_at: Utc::now().to_rfc3339(),
+        };
+        let Ok(json) = serde_json::to_string(&saved) else {
+            return;
+        };
+
+        // Write to a temporary file first so a crash never leaves a truncated estimate
+        let tmp = path.with_extension("json.tmp");
+        if fs::write(&tmp, json).is_ok() {
+            let _ = fs::rename(&tmp, path);
+        }
+    }
+}
+
+impl Drop for SocEstimator {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
+
+/// Linear interpolation on the resting voltage curve, clamped to its ends
+fn soc_from_voltage(curve: &[VoltageSoc], voltage: f64) -> f64 {
+    let mut points: Vec<&VoltageSoc> = curve.iter().collect();
+    points.sort_by(|a, b| a.voltage.total_cmp(&b.voltage));
+
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if voltage <= first.voltage {
+        return first.soc;
+    }
+    if voltage >= last.voltage {
+        return last.soc;
+    }
+    points
+        .windows(2)
+        .find(|pair| voltage <= pair[1].voltage)
+        .map(|pair| {
+            let (lo, hi) = (pair[0], pair[1]);
+            let span = (hi.voltage - lo.voltage).max(f64::EPSILON);
+            lo.soc + (voltage - lo.voltage) / span * (hi.soc - lo.soc)
+        })
+        .unwrap_or(last.soc)
+}
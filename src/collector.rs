@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+
+use crate::alerts::Alert;
+use crate::feed::Frame;
+use crate::http;
+use crate::state::{AppState, PowerData};
+
+/// Delay before an attached client retries a lost connection
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Newline-delimited JSON messages sent from a collector to attached clients
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// Full history, sent once on connect
+    History { data: Vec<PowerData> },
+    /// Latest reading after each successful fetch
+    Snapshot { data: Box<PowerData> },
+    /// Alert list, sent on connect and whenever it changes
+    Alerts { alerts: Vec<Alert> },
+    /// Fetch or connection failure
+    Error { message: String },
+}
+
+/// Collector address: `unix:/path/to.sock`, a path containing `/`, or TCP `host:port`
+enum Endpoint {
+    Tcp(String),
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Unix(PathBuf),
+}
+
+impl Endpoint {
+    fn parse(addr: &str) -> Self {
+        if let Some(path) = addr.strip_prefix("unix:") {
+            Endpoint::Unix(PathBuf::from(path))
+        } else if addr.contains('/') {
+            Endpoint::Unix(PathBuf::from(addr))
+        } else {
+            Endpoint::Tcp(addr.to_string())
+        }
+    }
+}
+
+/// Bind the collector endpoint and publish every frame to attached clients
+pub async fn start(addr: &str, frames: watch::Receiver<Arc<Frame>>) -> Result<()> {
+    match Endpoint::parse(addr) {
+        Endpoint::Tcp(addr) => {
+            let listener = TcpListener::bind(&addr)
+                .await
+                .with_context(|| format!("Failed to bind collector on {}", addr))?;
+            tokio::spawn(async move {
+                loop {
+                    let stream = http::accept(&listener, "collector").await;
+                    tokio::spawn(serve_client(stream, frames.clone()));
+                }
+            });
+        }
+        #[cfg(unix)]
+        Endpoint::Unix(path) => {
+            remove_stale_socket(&path)?;
+            let listener = tokio::net::UnixListener::bind(&path)
+                .with_context(|| format!("Failed to bind collector on {}", path.display()))?;
+            tokio::spawn(async move {
+                loop {
+                    let stream = http::accept(&listener, "collector").await;
+                    tokio::spawn(serve_client(stream, frames.clone()));
+                }
+            });
+        }
+        #[cfg(not(unix))]
+        Endpoint::Unix(path) => {
+            anyhow::bail!(
+                "Unix sockets are not supported on this platform: {}",
+                path.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove a socket left behind by a previous run; anything else at the path is an error
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return Ok(()); // Nothing there yet
+    };
+    if !metadata.file_type().is_socket() {
+        anyhow::bail!(
+            "Failed to bind collector on {}: the path exists and is not a socket",
+            path.display()
+        );
+    }
+    if std::os::unix::net::UnixStream::connect(path).is_ok() {
+        anyhow::bail!(
+            "Failed to bind collector on {}: already in use by a running collector",
+            path.display()
+        );
+    }
+    std::fs::remove_file(path)
+        .with_context(|| format!("Failed to remove stale socket {}", path.display()))
+}
+
+async fn serve_client<S>(mut stream: S, mut frames: watch::Receiver<Arc<Frame>>) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let frame = frames.borrow_and_update().clone();
+    write_message(
+        &mut stream,
+        &Message::History {
+            data: frame.history.clone(),
+        },
+    )
+    .await?;
+    let mut alerts = frame.alerts.clone();
+    write_message(
+        &mut stream,
+        &Message::Alerts {
+            alerts: alerts.clone(),
+        },
+    )
+    .await?;
+
+    while frames.changed().await.is_ok() {
+        let frame = frames.borrow_and_update().clone();
+        if frame.alerts != alerts {
+            alerts = frame.alerts.clone();
+            write_message(
+                &mut stream,
+                &Message::Alerts {
+                    alerts: alerts.clone(),
+                },
+            )
+            .await?;
+        }
+        let message = match (&frame.error, frame.latest()) {
+            (Some(error), _) => Message::Error {
+                message: error.clone(),
+            },
+            (None, Some(data)) => Message::Snapshot {
+                data: Box::new(data.clone()),
+            },
+            (None, None) => continue,
+        };
+        write_message(&mut stream, &message).await?;
+    }
+
+    Ok(())
+}
+
+async fn write_message<S>(stream: &mut S, message: &Message) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Connect to a collector in the background, reconnecting on failure
+pub fn attach(addr: String) -> mpsc::UnboundedReceiver<Message> {
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        loop {
+            let result = match Endpoint::parse(&addr) {
+                Endpoint::Tcp(host) => match TcpStream::connect(&host).await {
+                    Ok(stream) => read_messages(stream, &tx).await,
+                    Err(e) => Err(e.into()),
+                },
+                #[cfg(unix)]
+                Endpoint::Unix(path) => match tokio::net::UnixStream::connect(&path).await {
+                    Ok(stream) => read_messages(stream, &tx).await,
+                    Err(e) => Err(e.into()),
+                },
+                #[cfg(not(unix))]
+                Endpoint::Unix(_) => Err(anyhow::anyhow!(
+                    "Unix sockets are not supported on this platform"
+                )),
+            };
+
+            let message = match result {
+                Ok(()) => format!("Collector at {} closed the connection", addr),
+                Err(e) => format!("Failed to attach to collector at {}: {:#}", addr, e),
+            };
+            if tx.send(Message::Error { message }).is_err() {
+                return;
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+
+    rx
+}
+
+/// Apply a collector message to the local state
+pub fn apply(app: &mut AppState, message: Message) {
+    match message {
+        Message::History { data } => app.replace_history(data),
+        Message::Snapshot { data } => app.record(*data),
+        Message::Alerts { alerts } => app.alert_list = alerts,
+        Message::Error { message } => app.error = Some(message),
+    }
+}
+
+async fn read_messages<S>(stream: S, tx: &mpsc::UnboundedSender<Message>) -> Result<()>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(stream).lines();
+    while let Some(line) = lines.next_line().await? {
+        let message: Message =
+            serde_json::from_str(&line).context("Invalid message from collector")?;
+        if tx.send(message).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,292 @@
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::state::{PowerData, Severity};
+use crate::tariff::Tariff;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     pub home_assistant: HomeAssistantConfig,
+    #[serde(default)]
+    pub exporter: Option<ExporterConfig>,
+    #[serde(default)]
+    pub web: Option<WebConfig>,
+    #[serde(default)]
+    pub tariff: Option<TariffConfig>,
+    #[serde(default)]
+    pub roi: Option<RoiConfig>,
+    #[serde(default)]
+    pub alerts: Option<Vec<AlertRule>>, // None uses the built-in rules, `alerts = []` disables them
+    #[serde(default)]
+    pub publish: Option<PublishConfig>,
+    #[serde(default)]
+    pub actions: Vec<ActionConfig>,
+    #[serde(default)]
+    pub surplus: Option<SurplusConfig>,
+    #[serde(default)]
+    pub read_only: bool, // Never write to Home Assistant: no actions, publishing or load switching
+}
+
+/// Prometheus exporter settings (`[exporter]` section)
+#[derive(Debug, Deserialize, Clone)]
+pub struct ExporterConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_exporter_listen")]
+    pub listen: String, // Address to serve /metrics on, e.g. "127.0.0.1:9464"
+    #[serde(default = "default_site")]
+    pub site: String, // Value of the `site` label on every metric
+}
+
+/// Publish derived values back to Home Assistant (`[publish]` section)
+#[derive(Debug, Deserialize, Clone)]
+pub struct PublishConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_publish_prefix")]
+    pub prefix: String, // Entity id prefix, the metric name is appended
+    #[serde(default = "default_publish_metrics")]
+    pub metrics: Vec<String>, // Numeric fields, as printed by `emon snapshot`
+    pub interval: Option<String>, // How often states are pushed, default "60s"
+    #[serde(default)]
+    pub notifications: bool, // Mirror alerts as persistent notifications
+}
+
+impl PublishConfig {
+    pub fn get_interval(&self) -> Duration {
+        let seconds = self.interval.as_deref().and_then(parse_duration);
+        Duration::from_secs(seconds.unwrap_or(60) as u64)
+    }
+}
+
+fn default_publish_prefix() -> String {
+    "sensor.emon_".to_string()
+}
+
+fn default_publish_metrics() -> Vec<String> {
+    [
+        "self_sufficiency",
+        "battery_time_to_reserve_seconds",
+        "today_cost",
+        "alerts_active",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+/// One service call offered in the actions menu (`[[actions]]` sections)
+#[derive(Debug, Deserialize, Clone)]
+pub struct ActionConfig {
+    pub name: String,
+    pub service: String, // "domain.service", e.g. "switch.turn_on"
+    #[serde(default)]
+    pub data: toml::Table, // Service data, e.g. { entity_id = "switch.water_heater" }
+}
+
+impl ActionConfig {
+    /// Domain and service name; validated when the config is loaded
+    pub fn domain_service(&self) -> (&str, &str) {
+        self.service.split_once('.').unwrap_or((&self.service, ""))
+    }
+}
+
+/// Excess solar advisor (`[surplus]` section)
+#[derive(Debug, Deserialize, Clone)]
+pub struct SurplusConfig {
+    #[serde(default = "default_surplus_min_export")]
+    pub min_export: f64, // W of grid export that counts as surplus
+    #[serde(rename = "for")]
+    pub duration: Option<String>, // Surplus must last this long, default "5m"
+    #[serde(default = "default_true")]
+    pub require_float: bool, // Only while the battery is full (floating)
+    #[serde(default = "default_surplus_hysteresis")]
+    pub hysteresis: f64, // W of margin before switching a load on, and of import before switching off
+    #[serde(default)]
+    pub loads: Vec<SurplusLoad>,
+}
+
+impl SurplusConfig {
+    pub fn get_duration(&self) -> Duration {
+        let seconds = self.duration.as_deref().and_then(parse_duration);
+        Duration::from_secs(seconds.unwrap_or(300) as u64)
+    }
+}
+
+/// A deferrable load suggested during surplus (`[[surplus.loads]]`)
+#[derive(Debug, Deserialize, Clone)]
+pub struct SurplusLoad {
+    pub name: String,
+    pub power: f64,             // Typical draw in W
+    pub entity: Option<String>, // Switched with homeassistant.turn_on/turn_off when `auto`
+    #[serde(default)]
+    pub auto: bool,
+}
+
+fn default_surplus_min_export() -> f64 {
+    500.0
+}
+
+fn default_surplus_hysteresis() -> f64 {
+    200.0
+}
+
+/// One alert rule (`[[alerts]]` sections)
+#[derive(Debug, Deserialize, Clone)]
+pub struct AlertRule {
+    pub name: Option<String>, // Shown in the alerts panel, defaults to the metric
+    pub metric: String,       // Any numeric field, as printed by `emon snapshot`
+    pub comparison: Comparison,
+    pub threshold: f64,
+    #[serde(default)]
+    pub hysteresis: f64, // How far back past the threshold the value must go to clear
+    #[serde(rename = "for")]
+    pub duration: Option<String>, // Condition must hold this long before raising, e.g. "30s"
+    #[serde(default = "default_alert_severity")]
+    pub severity: Severity,
+
+    // Actions, run when the alert is raised (and `command` again when it clears)
+    pub command: Option<String>, // Run with `sh -c`, the alert as JSON on stdin
+    #[serde(default)]
+    pub bell: bool, // Ring the terminal bell (TUI only)
+    #[serde(default)]
+    pub notify: bool, // OSC 9/777 desktop notification (TUI only)
+    pub min_interval: Option<String>, // At most one round of actions per rule this often, default "5m"
+}
+
+impl AlertRule {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.metric)
+    }
+
+    /// Minimum duration before the alert is raised, zero when unset
+    pub fn get_duration(&self) -> Duration {
+        let seconds = self.duration.as_deref().and_then(parse_duration);
+        Duration::from_secs(seconds.unwrap_or(0) as u64)
+    }
+
+    /// Minimum time between two rounds of actions, 5 minutes when unset
+    pub fn get_min_interval(&self) -> Duration {
+        let seconds = self.min_interval.as_deref().and_then(parse_duration);
+        Duration::from_secs(seconds.unwrap_or(300) as u64)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Comparison {
+    #[serde(rename = ">")]
+    Above,
+    #[serde(rename = ">=")]
+    AtLeast,
+    #[serde(rename = "<")]
+    Below,
+    #[serde(rename = "<=")]
+    AtMost,
+}
+
+impl Comparison {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Above => ">",
+            Comparison::AtLeast => ">=",
+            Comparison::Below => "<",
+            Comparison::AtMost => "<=",
+        }
+    }
+}
+
+fn default_alert_severity() -> Severity {
+    Severity::Warning
+}
+
+/// Local web dashboard settings (`[web]` section)
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_web_listen")]
+    pub listen: String, // Address to serve the dashboard on, e.g. "0.0.0.0:8787" for the LAN
+}
+
+/// Electricity tariff used to compute costs locally (`[tariff]` section)
+///
+/// Every field overrides the corresponding value of `preset`, if one is named.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TariffConfig {
+    pub preset: Option<String>, // Built-in tariff, e.g. "pea-1.2.2" (see `tariff::PRESETS`)
+    pub currency: Option<String>,
+    pub rate: Option<f64>, // Off-peak rate per kWh, or the flat rate without `on_peak` windows
+    pub on_peak_rate: Option<f64>,
+    pub blocks: Option<Vec<RateBlock>>, // Progressive rates by kWh used in the billing cycle
+    pub on_peak: Option<Vec<TouWindow>>,
+    #[serde(default)]
+    pub holidays: Vec<String>, // YYYY-MM-DD dates that are off-peak all day
+    pub ft: Option<f64>, // FT surcharge per imported kWh
+    #[serde(default)]
+    pub ft_schedule: Vec<FtPeriod>,
+    pub service_charge: Option<f64>, // Fixed charge per billing cycle
+    pub vat_percent: Option<f64>,
+    pub export_rate: Option<f64>, // Feed-in credit per exported kWh
+    pub billing_day: Option<u32>, // Day of month the billing cycle starts (1-28)
+}
+
+/// Solar investment for payback tracking (`[roi]` section, needs `[tariff]`)
+#[derive(Debug, Deserialize, Clone)]
+pub struct RoiConfig {
+    pub installation_cost: f64,
+    pub installation_date: String, // YYYY-MM-DD
+    pub loan_amount: Option<f64>,
+    pub loan_interest_percent: Option<f64>, // Annual rate
+    pub loan_term_months: Option<u32>,
+}
+
+/// On-peak window, e.g. `{ days = ["mon", "fri"], start = "09:00", end = "22:00" }`
+#[derive(Debug, Deserialize, Clone)]
+pub struct TouWindow {
+    #[serde(default)]
+    pub days: Vec<String>, // Weekday names; empty means every day
+    pub start: String,
+    pub end: String, // Before `start` wraps past midnight
+}
+
+/// One progressive block: `rate` applies to cycle usage up to `up_to` kWh
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateBlock {
+    pub up_to: Option<f64>, // None for the last, open-ended block
+    pub rate: f64,
+}
+
+/// FT value in effect for billing cycles starting on or after `from` (YYYY-MM-DD)
+#[derive(Debug, Deserialize, Clone)]
+pub struct FtPeriod {
+    pub from: String,
+    pub ft: f64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_exporter_listen() -> String {
+    "127.0.0.1:9464".to_string()
+}
+
+fn default_web_listen() -> String {
+    "127.0.0.1:8787".to_string()
+}
+
+fn default_site() -> String {
+    "home".to_string()
+}
+
+/// One point of the resting voltage to SoC curve
+#[derive(Debug, Deserialize, Clone)]
+pub struct VoltageSoc {
+    pub voltage: f64,
+    pub soc: f64,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -17,6 +298,10 @@ pub struct HomeAssistantConfig {
     pub max_solar_power: Option<f64>,
     pub battery_float_voltage: Option<f64>,
     pub battery_capacity_kwh: Option<f64>,
+    pub battery_reserve_soc: Option<f64>, // SoC (%) treated as empty for time-to-empty
+    pub battery_voltage_soc: Option<Vec<VoltageSoc>>, // Resting voltage to SoC, for the SoC estimator
+    pub latitude: Option<f64>,                        // Site location for sunrise/sunset
+    pub longitude: Option<f64>,                       // Degrees, positive east
     #[serde(default)]
     pub history_duration: Option<String>,
     #[serde(default)]
@@ -76,6 +361,31 @@ impl HomeAssistantConfig {
         let seconds = self.history_seconds.unwrap_or(120) as f64;
         (seconds, "s".to_string(), "Time (s)".to_string())
     }
+
+    /// Get data fetch interval
+    /// 0 = realtime (~100ms), None = default 5s, Some(n) = n seconds
+    pub fn get_fetch_interval(&self) -> Duration {
+        let interval_ms = match self.fetch_interval_seconds {
+            Some(0) => 100,      // Realtime mode
+            Some(s) => s * 1000, // User-defined seconds to milliseconds
+            None => 5000,        // Default 5 seconds
+        };
+        Duration::from_millis(interval_ms)
+    }
+
+    /// SoC (%) the battery stops discharging at, default 20%
+    pub fn get_battery_reserve_soc(&self) -> f64 {
+        self.battery_reserve_soc.unwrap_or(20.0)
+    }
+
+    /// Get configured timezone, falling back to Asia/Bangkok if unset or invalid
+    pub fn get_timezone(&self) -> Tz {
+        self.timezone
+            .as_deref()
+            .unwrap_or("Asia/Bangkok")
+            .parse()
+            .unwrap_or(chrono_tz::Asia::Bangkok)
+    }
 }
 
 /// Format duration string for display
@@ -223,7 +533,92 @@ pub struct Entities {
     pub day_consume: Option<String>,
 }
 
-fn get_config_dir() -> Result<PathBuf> {
+impl Entities {
+    /// Optional entities with their setting names, in declaration order
+    fn optional(&self) -> Vec<(&'static str, &Option<String>)> {
+        vec![
+            ("load_current", &self.load_current),
+            ("battery_power", &self.battery_power),
+            ("battery_voltage", &self.battery_voltage),
+            ("battery_soc", &self.battery_soc),
+            ("battery_temp", &self.battery_temp),
+            ("battery_current", &self.battery_current),
+            ("inverter_temp", &self.inverter_temp),
+            ("inverter_voltage", &self.inverter_voltage),
+            ("inverter_status", &self.inverter_status),
+            ("grid_voltage", &self.grid_voltage),
+            ("grid_ct_power", &self.grid_ct_power),
+            ("inverter_frequency", &self.inverter_frequency),
+            ("load_power_factor", &self.load_power_factor),
+            ("grid_power_factor", &self.grid_power_factor),
+            ("day_battery_charge", &self.day_battery_charge),
+            ("day_battery_discharge", &self.day_battery_discharge),
+            ("day_grid_import", &self.day_grid_import),
+            ("day_grid_export", &self.day_grid_export),
+            ("day_load_energy", &self.day_load_energy),
+            ("day_pv_energy", &self.day_pv_energy),
+            ("total_pv_generation", &self.total_pv_generation),
+            ("remaining_solar", &self.remaining_solar),
+            ("dc_transformer_temp", &self.dc_transformer_temp),
+            ("radiator_temp", &self.radiator_temp),
+            ("essential_power", &self.essential_power),
+            (
+                "all_time_energy_usage_peak",
+                &self.all_time_energy_usage_peak,
+            ),
+            (
+                "all_time_energy_usage_peak_date",
+                &self.all_time_energy_usage_peak_date,
+            ),
+            ("all_time_load_peak", &self.all_time_load_peak),
+            ("all_time_pv_power_peak", &self.all_time_pv_power_peak),
+            (
+                "all_time_pv_power_peak_date",
+                &self.all_time_pv_power_peak_date,
+            ),
+            ("all_time_pv_yield_peak", &self.all_time_pv_yield_peak),
+            (
+                "all_time_pv_yield_peak_date",
+                &self.all_time_pv_yield_peak_date,
+            ),
+            ("daily_pv_power_peak", &self.daily_pv_power_peak),
+            ("daily_pv_power_peak_date", &self.daily_pv_power_peak_date),
+            ("night_consume", &self.night_consume),
+            ("pv_forecast_remain", &self.pv_forecast_remain),
+            ("pv_forecast_today", &self.pv_forecast_today),
+            ("load_energy_yesterday", &self.load_energy_yesterday),
+            ("load_energy_total", &self.load_energy_total),
+            ("day_consume", &self.day_consume),
+        ]
+    }
+
+    /// Configured entity ids with their setting names, in declaration order
+    pub fn configured(&self) -> Vec<(&'static str, &str)> {
+        let mut entities = vec![
+            ("solar_production", self.solar_production.as_str()),
+            ("grid_import", self.grid_import.as_str()),
+            ("grid_export", self.grid_export.as_str()),
+            ("load_consumption", self.load_consumption.as_str()),
+        ];
+        entities.extend(
+            self.optional()
+                .into_iter()
+                .filter_map(|(name, entity)| Some((name, entity.as_deref()?))),
+        );
+        entities
+    }
+
+    /// Setting names of optional entities that are not configured; their reading is 0
+    pub fn unconfigured(&self) -> Vec<&'static str> {
+        self.optional()
+            .into_iter()
+            .filter(|(_, entity)| entity.is_none())
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+pub fn get_config_dir() -> Result<PathBuf> {
     let home = std::env::var("HOME")
         .or_else(|_| std::env::var("USERPROFILE"))
         .context("Failed to determine home directory")?;
@@ -264,6 +659,130 @@ pub fn load_config_at(custom_path: Option<&Path>) -> Result<Config> {
     let config: Config = toml::from_str(&content)
         .with_context(|| format!("Failed to parse config file from {}", path_str))?;
 
+    if let Some(tariff) = &config.tariff {
+        Tariff::from_config(tariff)
+            .with_context(|| format!("Invalid [tariff] in config file {}", path_str))?;
+    }
+    if let Some(curve) = &config.home_assistant.battery_voltage_soc {
+        if curve.len() < 2 || curve.iter().any(|p| !(0.0..=100.0).contains(&p.soc)) {
+            anyhow::bail!(
+                "Invalid battery_voltage_soc in config file {}, expected at least two points with soc between 0 and 100",
+                path_str
+            );
+        }
+    }
+    for rule in config.alerts.iter().flatten() {
+        for (key, value) in [
+            ("for", &rule.duration),
+            ("min_interval", &rule.min_interval),
+        ] {
+            if value
+                .as_deref()
+                .is_some_and(|d| parse_duration(d).is_none())
+            {
+                anyhow::bail!(
+                    "Invalid `{}` duration in alert '{}' in config file {}, expected e.g. \"30s\", \"5m\"",
+                    key,
+                    rule.label(),
+                    path_str
+                );
+            }
+        }
+        if !PowerData::METRICS.contains(&rule.metric.as_str()) {
+            anyhow::bail!(
+                "Unknown metric '{}' in alert '{}' in config file {}, expected a numeric field as printed by `emon snapshot`",
+                rule.metric,
+                rule.label(),
+                path_str
+            );
+        }
+        // Counted from the alerts the rules raise, so a rule on it would never see a value
+        if rule.metric == "alerts_active" {
+            anyhow::bail!(
+                "Alert '{}' in config file {} cannot use metric 'alerts_active', it is counted after the rules are evaluated",
+                rule.label(),
+                path_str
+            );
+        }
+        if rule.severity == Severity::Normal {
+            anyhow::bail!(
+                "Alert '{}' in config file {} must have severity \"warning\" or \"critical\"",
+                rule.label(),
+                path_str
+            );
+        }
+    }
+    if let Some(publish) = &config.publish {
+        if !publish.prefix.contains('.') {
+            anyhow::bail!(
+                "Invalid [publish] prefix '{}' in config file {}, expected a domain such as \"sensor.emon_\"",
+                publish.prefix,
+                path_str
+            );
+        }
+        if publish
+            .interval
+            .as_deref()
+            .is_some_and(|d| parse_duration(d).is_none())
+        {
+            anyhow::bail!(
+                "Invalid [publish] interval in config file {}, expected e.g. \"60s\", \"5m\"",
+                path_str
+            );
+        }
+        if let Some(metric) = publish
+            .metrics
+            .iter()
+            .find(|m| !PowerData::METRICS.contains(&m.as_str()))
+        {
+            anyhow::bail!(
+                "Unknown [publish] metric '{}' in config file {}, expected a numeric field as printed by `emon snapshot`",
+                metric,
+                path_str
+            );
+        }
+    }
+    for action in &config.actions {
+        let (domain, service) = action.domain_service();
+        if domain.is_empty() || service.is_empty() || service.contains('.') {
+            anyhow::bail!(
+                "Invalid service '{}' for action '{}' in config file {}, expected \"domain.service\" such as \"switch.turn_on\"",
+                action.service,
+                action.name,
+                path_str
+            );
+        }
+    }
+    if let Some(surplus) = &config.surplus {
+        if surplus
+            .duration
+            .as_deref()
+            .is_some_and(|d| parse_duration(d).is_none())
+        {
+            anyhow::bail!(
+                "Invalid [surplus] for in config file {}, expected e.g. \"5m\", \"300s\"",
+                path_str
+            );
+        }
+        if let Some(load) = surplus.loads.iter().find(|l| l.auto && l.entity.is_none()) {
+            anyhow::bail!(
+                "Surplus load '{}' in config file {} has auto = true but no entity to switch",
+                load.name,
+                path_str
+            );
+        }
+    }
+    if let Some(roi) = &config.roi {
+        chrono::NaiveDate::parse_from_str(&roi.installation_date, "%Y-%m-%d").with_context(
+            || {
+                format!(
+                    "Invalid [roi] installation_date '{}' in config file {}, expected YYYY-MM-DD",
+                    roi.installation_date, path_str
+                )
+            },
+        )?;
+    }
+
     Ok(config)
 }
 
@@ -275,6 +794,12 @@ token = "your_long_lived_access_token_here"
 max_solar_power = 18000.0  # Maximum solar power in Watts (used for gauge scaling and history chart Y-axis)
 battery_float_voltage = 54.0  # Battery floating voltage threshold (e.g., 54V for 48V battery system)
 battery_capacity_kwh = 15.36  # Total battery capacity in kWh (e.g., 15.36 kWh for a 48V 320Ah system)
+battery_reserve_soc = 20.0  # SoC (%) the battery stops discharging at, used for time-to-empty (default: 20)
+# Without a battery_soc sensor, SoC is estimated by integrating battery power (or current x voltage).
+# An optional resting voltage curve sets the starting point and corrects drift while idle:
+# battery_voltage_soc = [{ voltage = 48.0, soc = 0 }, { voltage = 51.2, soc = 50 }, { voltage = 53.2, soc = 100 }]
+# latitude = 13.75  # Site location for sunrise/sunset (overnight battery forecast)
+# longitude = 100.5
 history_duration = "120s"  # History duration with unit: s/m/h (e.g., "180s", "3m", "1h", default: "120s")
 timezone = "Asia/Bangkok"  # Timezone for display (default: Asia/Bangkok)
 max_daily_energy = 100.0  # Maximum expected daily energy in kWh for bar chart scaling (default: 100.0)
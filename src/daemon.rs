@@ -0,0 +1,220 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tokio::time::MissedTickBehavior;
+
+use crate::alerts::{Alert, AlertWatcher};
+use crate::config::Config;
+use crate::feed::Feed;
+use crate::headless::shutdown_signal;
+use crate::state::{AppState, Role, Severity};
+
+/// Log level of a daemon event
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    /// syslog priority used by journald's stdout stream parser
+    fn syslog_priority(self) -> u8 {
+        match self {
+            Level::Debug => 7,
+            Level::Info => 6,
+            Level::Warning => 4,
+            Level::Error => 3,
+        }
+    }
+}
+
+/// Writes one JSON object per event to a file or stdout
+pub struct EventLog {
+    out: Box<dyn Write + Send>,
+    journald: bool,
+    verbose: bool,
+}
+
+impl EventLog {
+    pub fn new(log_file: Option<&Path>, verbose: bool) -> Result<Self> {
+        let out: Box<dyn Write + Send> = match log_file {
+            Some(path) => Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Failed to open log file {}", path.display()))?,
+            ),
+            None => Box::new(io::stdout()),
+        };
+
+        // systemd sets JOURNAL_STREAM when stdout is connected to the journal
+        let journald = log_file.is_none() && std::env::var_os("JOURNAL_STREAM").is_some();
+
+        Ok(Self {
+            out,
+            journald,
+            verbose,
+        })
+    }
+
+    pub fn event(&mut self, level: Level, event: &str, fields: Value) {
+        if matches!(level, Level::Debug) && !self.verbose {
+            return;
+        }
+
+        let mut record = json!({
+            "ts": Utc::now().to_rfc3339(),
+            "level": level,
+            "event": event,
+        });
+        if let (Some(record), Value::Object(fields)) = (record.as_object_mut(), fields) {
+            record.extend(fields);
+        }
+
+        let prefix = if self.journald {
+            format!("<{}>", level.syslog_priority())
+        } else {
+            String::new()
+        };
+        let _ = writeln!(self.out, "{}{}", prefix, record);
+        let _ = self.out.flush();
+    }
+}
+
+/// Fetch and log continuously without a terminal until SIGTERM or Ctrl-C
+pub async fn run_daemon(
+    config: Config,
+    config_path: Option<PathBuf>,
+    log_file: Option<PathBuf>,
+    verbose: bool,
+    listen: Option<String>,
+) -> Result<()> {
+    let mut log = EventLog::new(log_file.as_deref(), verbose)?;
+
+    let feed = Feed::new();
+    feed.start_servers(&config, listen.as_deref()).await?;
+    if let Some(exporter_config) = config.exporter.as_ref().filter(|e| e.enabled) {
+        log.event(
+            Level::Info,
+            "exporter_started",
+            json!({ "listen": exporter_config.listen }),
+        );
+    }
+    if let Some(addr) = &listen {
+        log.event(Level::Info, "collector_started", json!({ "listen": addr }));
+    }
+
+    let mut app = AppState::new(config, config_path, Role::Owner);
+    let interval = app.config.home_assistant.get_fetch_interval();
+    log.event(
+        Level::Info,
+        "started",
+        json!({
+            "url": app.config.home_assistant.url,
+            "interval_ms": interval.as_millis() as u64,
+            // False while another emon owns polling: no hooks, publishing or load switching
+            "owner": app.role == Role::Owner,
+        }),
+    );
+
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut alert_watcher = AlertWatcher::default();
+    let mut failing = false;
+
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = &mut shutdown => break,
+        }
+
+        match app.update().await {
+            Ok(()) => {
+                if failing {
+                    log.event(Level::Info, "fetch_recovered", json!({}));
+                    failing = false;
+                }
+                if let Some(data) = app.history.last() {
+                    // Unavailable readings are logged as null
+                    let value = |metric, value| data.is_available(metric).then_some(value);
+                    log.event(
+                        Level::Debug,
+                        "fetch",
+                        json!({
+                            "duration_ms": app.last_update_duration.map(|d| d.as_millis() as u64),
+                            "solar": value("solar", data.solar),
+                            "load": value("load", data.load),
+                            "grid_import": value("grid_import", data.grid_import),
+                            "grid_export": value("grid_export", data.grid_export),
+                            "battery_power": value("battery_power", data.battery_power),
+                            "battery_soc": value("battery_soc", data.battery_soc),
+                            "unavailable": data.unavailable,
+                        }),
+                    );
+                    log_alert_changes(&mut log, &app.alert_list, &mut alert_watcher);
+                }
+            }
+            Err(e) => {
+                app.error = Some(e.to_string());
+                log.event(
+                    Level::Error,
+                    "fetch_failed",
+                    json!({ "error": format!("{:#}", e) }),
+                );
+                failing = true;
+            }
+        }
+        for error in app.publish_failures() {
+            log.event(Level::Error, "publish_failed", json!({ "error": error }));
+        }
+        feed.publish(&app);
+    }
+
+    log.event(Level::Info, "stopped", json!({}));
+    Ok(())
+}
+
+/// Log alerts raised or cleared by the latest reading
+fn log_alert_changes(log: &mut EventLog, alerts: &[Alert], watcher: &mut AlertWatcher) {
+    let (raised, cleared) = watcher.changes(alerts);
+    for alert in raised {
+        let level = match alert.severity {
+            Severity::Critical => Level::Error,
+            _ => Level::Warning,
+        };
+        log.event(
+            level,
+            "alert_raised",
+            json!({
+                "name": alert.name,
+                "metric": alert.metric,
+                "severity": alert.severity,
+                "value": alert.value,
+                "threshold": alert.threshold,
+            }),
+        );
+    }
+    for alert in cleared {
+        log.event(
+            Level::Info,
+            "alert_cleared",
+            json!({
+                "name": alert.name,
+                "metric": alert.metric,
+                "value": alert.value,
+                "raised_at": alert.raised_at,
+            }),
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::config::{self, Entities};
+use crate::state::PowerData;
+
+/// Gaps longer than this (e.g. Home Assistant outages) are not integrated
+const MAX_INTEGRATION_GAP: Duration = Duration::from_secs(300);
+
+/// How often the day's integrated totals are written to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where today's PV energy went and where today's load came from (kWh)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnergyBreakdown {
+    pub pv_total: f64,
+    pub pv_to_load: f64,
+    pub pv_to_battery: f64,
+    pub pv_to_grid: f64,
+
+    pub load_total: f64,
+    pub load_from_pv: f64,
+    pub load_from_battery: f64,
+    pub load_from_grid: f64,
+}
+
+impl EnergyBreakdown {
+    /// Split the `day_*` counters into flows.
+    ///
+    /// Export and battery charging are assumed to come from PV first; whatever
+    /// PV cannot cover was charged from the grid.
+    pub fn from_daily(data: &PowerData) -> Self {
+        let pv = data.day_pv_energy.max(0.0);
+        let export = data.day_grid_export.max(0.0);
+        let import = data.day_grid_import.max(0.0);
+        let charge = data.day_battery_charge.max(0.0);
+        let discharge = data.day_battery_discharge.max(0.0);
+
+        let pv_to_grid = export.min(pv);
+        let pv_to_battery = charge.min(pv - pv_to_grid);
+        let pv_to_load = pv - pv_to_grid - pv_to_battery;
+
+        let grid_to_battery = charge - pv_to_battery;
+        let load_from_grid = (import - grid_to_battery).max(0.0);
+
+        Self {
+            pv_total: pv,
+            pv_to_load,
+            pv_to_battery,
+            pv_to_grid,
+            load_total: data.day_load_energy.max(0.0),
+            load_from_pv: pv_to_load,
+            load_from_battery: discharge,
+            load_from_grid,
+        }
+    }
+
+    /// PV destinations as (label, kWh) in display order
+    pub fn pv_flows(&self) -> [(&'static str, f64); 3] {
+        [
+            ("Home", self.pv_to_load),
+            ("Battery", self.pv_to_battery),
+            ("Grid Export", self.pv_to_grid),
+        ]
+    }
+
+    /// Load sources as (label, kWh) in display order
+    pub fn load_flows(&self) -> [(&'static str, f64); 3] {
+        [
+            ("Solar", self.load_from_pv),
+            ("Battery", self.load_from_battery),
+            ("Grid", self.load_from_grid),
+        ]
+    }
+}
+
+/// Share of `part` in the sum of all flows, in percent
+pub fn share(part: f64, flows: &[(&str, f64)]) -> f64 {
+    let total: f64 = flows.iter().map(|(_, v)| v).sum();
+    if total > 0.0 {
+        part / total * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Share of PV used on site: (PV − export) / PV, in percent (0 without PV)
+pub fn self_consumption(pv: f64, export: f64) -> f64 {
+    if pv > 0.0 {
+        ((pv - export) / pv * 100.0).clamp(0.0, 100.0)
+    } else {
+        0.0
+    }
+}
+
+/// Share of load not covered by the grid: 1 − import / load, in percent (0 without load)
+pub fn self_sufficiency(load: f64, import: f64) -> f64 {
+    if load > 0.0 {
+        ((1.0 - import / load) * 100.0).clamp(0.0, 100.0)
+    } else {
+        0.0
+    }
+}
+
+/// Today's kWh integrated locally from power samples, for `day_*` sensors that are not configured
+///
+/// The totals are kept in `~/.emon/daily_energy.json` so a restart resumes the day instead of
+/// counting from zero; energy flowing while emon was not running is still missed.
+#[derive(Debug)]
+pub struct EnergyIntegrator {
+    day: Option<NaiveDate>,
+    last: Option<(Instant, [Option<f64>; 6])>, // None where the power was unavailable
+    totals: [f64; 6],                          // kWh, in the order of `DAILY_FIELDS`
+    restored: Option<SavedDay>,                // Resumed on the first sample if still that day
+    path: Option<PathBuf>,
+    last_save: Instant,
+}
+
+/// Integrated totals as saved on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedDay {
+    day: String, // Local date, YYYY-MM-DD
+    totals: [f64; 6],
+}
+
+/// Daily counters that can be integrated locally
+const DAILY_FIELDS: [&str; 6] = [
+    "day_pv_energy",
+    "day_load_energy",
+    "day_grid_import",
+    "day_grid_export",
+    "day_battery_charge",
+    "day_battery_discharge",
+];
+
+impl EnergyIntegrator {
+    /// Without `persist` the saved totals are read but never written back
+    pub fn new(persist: bool) -> Self {
+        let path = config::get_config_dir()
+            .ok()
+            .map(|dir| dir.join("daily_energy.json"));
+        let restored = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok());
+
+        Self {
+            day: None,
+            last: None,
+            totals: [0.0; 6],
+            restored,
+            path: path.filter(|_| persist),
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Add a sample taken on local date `today`, then fill in every
+    /// `day_*` counter whose entity is missing and record it in `computed_daily`
+    pub fn apply(&mut self, data: &mut PowerData, entities: &Entities, today: NaiveDate) {
+        // Counters reset at local midnight
+        if self.day != Some(today) {
+            let key = today.format("%Y-%m-%d").to_string();
+            self.day = Some(today);
+            self.totals = match self.restored.take() {
+                Some(saved) if saved.day == key => saved.totals,
+                _ => [0.0; 6],
+            };
+            self.last = None;
+        }
+
+        let sample = |metric: &str, value: f64| data.is_available(metric).then_some(value);
+        let powers = [
+            sample("solar", data.solar),
+            sample("load", data.load),
+            sample("grid_import", data.grid_import),
+            sample("grid_export", data.grid_export),
+            sample("battery_power", data.battery_power.max(0.0)),
+            sample("battery_power", (-data.battery_power).max(0.0)),
+        ];
+
+        if let Some((last_at, last_powers)) = self.last {
+            let dt = data.timestamp.saturating_duration_since(last_at);
+            if dt <= MAX_INTEGRATION_GAP {
+                let hours = dt.as_secs_f64() / 3600.0;
+                for (total, (p0, p1)) in self.totals.iter_mut().zip(last_powers.iter().zip(&powers))
+                {
+                    // Trapezoidal rule, W·h → kWh; intervals next to an unavailable sample are skipped
+                    if let (Some(p0), Some(p1)) = (p0, p1) {
+                        *total += (p0 + p1) / 2.0 * hours / 1000.0;
+                    }
+                }
+            }
+        }
+        self.last = Some((data.timestamp, powers));
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+
+        let configured = [
+            entities.day_pv_energy.is_some(),
+            entities.day_load_energy.is_some(),
+            entities.day_grid_import.is_some(),
+            entities.day_grid_export.is_some(),
+            entities.day_battery_charge.is_some(),
+            entities.day_battery_discharge.is_some(),
+        ];
+        let targets = [
+            &mut data.day_pv_energy,
+            &mut data.day_load_energy,
+            &mut data.day_grid_import,
+            &mut data.day_grid_export,
+            &mut data.day_battery_charge,
+            &mut data.day_battery_discharge,
+        ];
+
+        data.computed_daily.clear();
+        for (idx, target) in targets.into_iter().enumerate() {
+            if !configured[idx] {
+                *target = self.totals[idx];
+                data.computed_daily.push(DAILY_FIELDS[idx].to_string());
+            }
+        }
+    }
+
+    fn save(&mut self) {
+        self.last_save = Instant::now();
+        let (Some(path), Some(day)) = (&self.path, self.day) else {
+            return;
+        };
+        let saved = SavedDay {
+            day: day.format("%Y-%m-%d").to_string(),
+            totals: self.totals,
+        };
+        let Ok(json) = serde_json::to_string(&saved) else {
+            return;
+        };
+
+        // Write to a temporary file first so a crash never leaves truncated totals
+        let tmp = path.with_extension("json.tmp");
+        if fs::write(&tmp, json).is_ok() {
+            let _ = fs::rename(&tmp, path);
+        }
+    }
+}
+
+impl Drop for EnergyIntegrator {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
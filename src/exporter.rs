@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+use crate::config::{Entities, ExporterConfig};
+use crate::feed::Frame;
+use crate::http;
+
+/// Bind the exporter port and serve `/metrics` in the background
+pub async fn start(
+    config: &ExporterConfig,
+    entities: &Entities,
+    frames: watch::Receiver<Arc<Frame>>,
+) -> Result<()> {
+    let listener = TcpListener::bind(&config.listen)
+        .await
+        .with_context(|| format!("Failed to bind exporter on {}", config.listen))?;
+    let site = config.site.clone();
+    let unconfigured = Arc::new(entities.unconfigured());
+
+    tokio::spawn(async move {
+        loop {
+            let mut stream = http::accept(&listener, "exporter").await;
+            let frame = frames.borrow().clone();
+            let site = site.clone();
+            let unconfigured = unconfigured.clone();
+
+            tokio::spawn(async move {
+                let Ok(request) = http::read_request(&mut stream).await else {
+                    return;
+                };
+                let _ = match (request.method.as_str(), request.path.as_str()) {
+                    ("GET", "/metrics") => {
+                        http::write_response(
+                            &mut stream,
+                            "200 OK",
+                            "text/plain; version=0.0.4; charset=utf-8",
+                            render_metrics(&frame, &site, &unconfigured).as_bytes(),
+                        )
+                        .await
+                    }
+                    _ => {
+                        http::write_response(&mut stream, "404 Not Found", "text/plain", b"").await
+                    }
+                };
+            });
+        }
+    });
+
+    Ok(())
+}
+
+/// Render the frame in Prometheus text exposition format; `unconfigured` sensors are left out
+fn render_metrics(frame: &Frame, site: &str, unconfigured: &[&str]) -> String {
+    let site = escape_label(site);
+    let mut out = String::new();
+
+    let gauge = |out: &mut String, name: &str, help: &str, value: f64| {
+        let _ = writeln!(out, "# HELP emon_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE emon_{} gauge", name);
+        let _ = writeln!(out, "emon_{}{{site=\"{}\"}} {}", name, site, value);
+    };
+
+    gauge(
+        &mut out,
+        "up",
+        "Whether the last Home Assistant fetch succeeded",
+        if frame.up { 1.0 } else { 0.0 },
+    );
+
+    if let Some(data) = frame.latest() {
+        for (name, value) in data.numeric_fields() {
+            // A sensor without an entity reads 0, unless emon computes it (daily counters, SoC)
+            let computed = data.computed_daily.iter().any(|field| field == name)
+                || (name == "battery_soc" && data.battery_soc_estimated);
+            if unconfigured.contains(&name) && !computed {
+                continue;
+            }
+            gauge(
+                &mut out,
+                name,
+                &format!("Home Assistant reading {}", name),
+                value,
+            );
+        }
+        gauge(
+            &mut out,
+            "battery_floating",
+            "Whether the battery is at float voltage",
+            if frame.battery_floating { 1.0 } else { 0.0 },
+        );
+
+        let _ = writeln!(out, "# HELP emon_inverter_status_info Inverter status text");
+        let _ = writeln!(out, "# TYPE emon_inverter_status_info gauge");
+        let _ = writeln!(
+            out,
+            "emon_inverter_status_info{{site=\"{}\",status=\"{}\"}} 1",
+            site,
+            escape_label(&data.inverter_status)
+        );
+    }
+
+    if let Some(duration) = frame.last_update_duration {
+        gauge(
+            &mut out,
+            "fetch_duration_seconds",
+            "Duration of the last full fetch cycle",
+            duration.as_secs_f64(),
+        );
+    }
+
+    let counters = [
+        (
+            "ha_requests_total",
+            "Home Assistant API requests",
+            frame.stats.requests as f64,
+        ),
+        (
+            "ha_request_errors_total",
+            "Failed Home Assistant API requests",
+            frame.stats.errors as f64,
+        ),
+        (
+            "ha_request_duration_seconds_total",
+            "Cumulative Home Assistant API request latency",
+            frame.stats.latency_seconds_total,
+        ),
+    ];
+    for (name, help, value) in counters {
+        let _ = writeln!(out, "# HELP emon_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE emon_{} counter", name);
+        let _ = writeln!(out, "emon_{}{{site=\"{}\"}} {}", name, site, value);
+    }
+
+    out
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::alerts::Alert;
+use crate::collector;
+use crate::config::Config;
+use crate::exporter;
+use crate::homeassistant::FetchStats;
+use crate::state::{AppState, PowerData};
+use crate::web;
+
+/// Read-only copy of the latest fetch, shared with background servers
+#[derive(Debug, Clone, Default)]
+pub struct Frame {
+    pub history: Vec<PowerData>,
+    pub alerts: Vec<Alert>, // Active alerts, then recently cleared ones
+    pub error: Option<String>,
+    pub battery_floating: bool,
+    pub up: bool,
+    pub stats: FetchStats,
+    pub last_update_duration: Option<Duration>,
+}
+
+impl Frame {
+    fn from_app(app: &AppState) -> Self {
+        let battery_floating = app
+            .history
+            .last()
+            .map(|d| app.is_battery_floating(d.battery_voltage, d.battery_power))
+            .unwrap_or(false);
+
+        Self {
+            history: app.history.clone(),
+            alerts: app.alert_list.clone(),
+            error: app.error.clone(),
+            battery_floating,
+            up: !app.history.is_empty() && app.error.is_none(),
+            stats: app.ha.stats(),
+            last_update_duration: app.last_update_duration,
+        }
+    }
+
+    pub fn latest(&self) -> Option<&PowerData> {
+        self.history.last()
+    }
+}
+
+/// Publishes a new frame after every fetch attempt
+#[derive(Debug)]
+pub struct Feed {
+    tx: watch::Sender<Arc<Frame>>,
+}
+
+impl Feed {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(Arc::new(Frame::default()));
+        Self { tx }
+    }
+
+    pub fn publish(&self, app: &AppState) {
+        self.tx.send_replace(Arc::new(Frame::from_app(app)));
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Frame>> {
+        self.tx.subscribe()
+    }
+
+    /// Bind the optional background servers that read from this feed
+    pub async fn start_servers(&self, config: &Config, listen: Option<&str>) -> Result<()> {
+        if let Some(exporter_config) = config.exporter.as_ref().filter(|e| e.enabled) {
+            exporter::start(
+                exporter_config,
+                &config.home_assistant.entities,
+                self.subscribe(),
+            )
+            .await?;
+        }
+        if let Some(addr) = listen {
+            collector::start(addr, self.subscribe()).await?;
+        }
+        web::start(config, self.subscribe()).await?;
+        Ok(())
+    }
+}
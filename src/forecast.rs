@@ -0,0 +1,235 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::config::{self, HomeAssistantConfig};
+use crate::state::PowerData;
+use crate::sun;
+
+/// Nights kept in ~/.emon/night_consumption.json
+const LEARNED_NIGHTS: usize = 14;
+
+/// Most recent learned nights averaged for the forecast
+const AVERAGE_NIGHTS: usize = 7;
+
+/// A night is only learned when at least this share of it was observed
+const MIN_COVERAGE: f64 = 0.8;
+
+/// Gaps longer than this are not integrated
+const MAX_GAP: Duration = Duration::from_secs(300);
+
+/// Where the expected night consumption comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NightSource {
+    Sensor,
+    Learned,
+}
+
+/// Will the battery last until sunrise?
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OvernightForecast {
+    pub sunset: String,  // RFC 3339, local time
+    pub sunrise: String, // Next sunrise, RFC 3339, local time
+    pub is_night: bool,
+    pub available_kwh: Option<f64>, // Stored energy above the reserve SoC; None when unknown
+    pub night_kwh: Option<f64>,     // Expected consumption for a whole night; None while learning
+    pub night_source: Option<NightSource>,
+    pub needed_kwh: Option<f64>, // Expected consumption from now until sunrise
+    pub shortfall_kwh: Option<f64>, // Expected grid import before sunrise
+}
+
+impl OvernightForecast {
+    pub fn numeric_fields(&self) -> Vec<(&'static str, f64)> {
+        let optional = [
+            ("overnight_available_kwh", self.available_kwh),
+            ("overnight_needed_kwh", self.needed_kwh),
+            ("overnight_shortfall_kwh", self.shortfall_kwh),
+        ];
+        optional
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)))
+            .collect()
+    }
+}
+
+/// Consumption of the night in progress
+#[derive(Debug)]
+struct NightInProgress {
+    sunrise: DateTime<Utc>,
+    length: f64, // Seconds from sunset to sunrise
+    kwh: f64,
+    covered: f64, // Seconds actually observed
+    last: Option<(Instant, f64)>,
+}
+
+/// Learns night consumption locally and forecasts overnight battery sufficiency
+#[derive(Debug)]
+pub struct NightForecaster {
+    learned: VecDeque<f64>,
+    current: Option<NightInProgress>,
+    path: Option<PathBuf>,
+}
+
+impl NightForecaster {
+    /// Learned nights are only saved with `persist`, i.e. by the process that owns polling
+    pub fn new(persist: bool) -> Self {
+        let path = config::get_config_dir()
+            .ok()
+            .map(|dir| dir.join("night_consumption.json"));
+        let learned = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            learned,
+            current: None,
+            path: path.filter(|_| persist),
+        }
+    }
+
+    pub fn apply(&mut self, data: &mut PowerData, config: &HomeAssistantConfig, tz: Tz) {
+        data.overnight = None;
+        let (Some(latitude), Some(longitude)) = (config.latitude, config.longitude) else {
+            return;
+        };
+
+        let now = Utc::now();
+        let today = now.with_timezone(&tz).date_naive();
+        let sun_on = |offset: i64| {
+            sun::sunrise_sunset(today + ChronoDuration::days(offset), latitude, longitude)
+        };
+        let (Some((sunrise_today, sunset_today)), Some((sunrise_tomorrow, _))) =
+            (sun_on(0), sun_on(1))
+        else {
+            return; // Polar day or night
+        };
+
+        // The night that is in progress, or the next one during daytime
+        let (sunset, sunrise, is_night) = if now < sunrise_today {
+            let sunset_yesterday = sun_on(-1).map(|(_, set)| set).unwrap_or(sunrise_today);
+            (sunset_yesterday, sunrise_today, true)
+        } else if now >= sunset_today {
+            (sunset_today, sunrise_tomorrow, true)
+        } else {
+            (sunset_today, sunrise_tomorrow, false)
+        };
+        let length = (sunrise - sunset).num_seconds().max(1) as f64;
+
+        self.learn(data, is_night, sunrise, length);
+
+        let (night_kwh, night_source) =
+            if config.entities.night_consume.is_some() && data.night_consume > 0.0 {
+                (Some(data.night_consume), Some(NightSource::Sensor))
+            } else if !self.learned.is_empty() {
+                let recent: Vec<f64> = self
+                    .learned
+                    .iter()
+                    .rev()
+                    .take(AVERAGE_NIGHTS)
+                    .copied()
+                    .collect();
+                (
+                    Some(recent.iter().sum::<f64>() / recent.len() as f64),
+                    Some(NightSource::Learned),
+                )
+            } else {
+                (None, None)
+            };
+
+        let capacity = config.battery_capacity_kwh.unwrap_or(0.0);
+        let reserve_kwh = config.get_battery_reserve_soc() / 100.0 * capacity;
+        // Unknown without a capacity or an SoC reading, which is not the same as an empty battery
+        let available_kwh = data
+            .battery_stored_kwh
+            .map(|stored| (stored - reserve_kwh).max(0.0));
+
+        // During the night only the part until sunrise is still needed
+        let remaining_share = if is_night {
+            ((sunrise - now).num_seconds() as f64 / length).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let needed_kwh = night_kwh.map(|kwh| kwh * remaining_share);
+
+        data.overnight = Some(OvernightForecast {
+            sunset: sunset.with_timezone(&tz).to_rfc3339(),
+            sunrise: sunrise.with_timezone(&tz).to_rfc3339(),
+            is_night,
+            available_kwh,
+            night_kwh,
+            night_source,
+            needed_kwh,
+            shortfall_kwh: needed_kwh
+                .zip(available_kwh)
+                .map(|(needed, available)| (needed - available).max(0.0)),
+        });
+    }
+
+    /// Integrate load during the night and remember the total once the night is over
+    fn learn(&mut self, data: &PowerData, is_night: bool, sunrise: DateTime<Utc>, length: f64) {
+        let finished = match &self.current {
+            Some(night) => !is_night || night.sunrise != sunrise,
+            None => false,
+        };
+        if finished {
+            if let Some(night) = self.current.take() {
+                if night.covered / night.length >= MIN_COVERAGE {
+                    // Scale up for the unobserved part of the night
+                    self.learned
+                        .push_back(night.kwh * night.length / night.covered);
+                    while self.learned.len() > LEARNED_NIGHTS {
+                        self.learned.pop_front();
+                    }
+                    self.save();
+                }
+            }
+        }
+
+        if !is_night {
+            return;
+        }
+        let night = self.current.get_or_insert(NightInProgress {
+            sunrise,
+            length,
+            kwh: 0.0,
+            covered: 0.0,
+            last: None,
+        });
+        // Unavailable load readings are left out of the learned consumption
+        if !data.is_available("load") {
+            night.last = None;
+            return;
+        }
+        if let Some((at, load)) = night.last {
+            let dt = data.timestamp.saturating_duration_since(at);
+            if dt <= MAX_GAP {
+                let seconds = dt.as_secs_f64();
+                night.kwh += (load + data.load) / 2.0 * seconds / 3600.0 / 1000.0;
+                night.covered += seconds;
+            }
+        }
+        night.last = Some((data.timestamp, data.load));
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let Ok(json) = serde_json::to_string(&self.learned) else {
+            return;
+        };
+
+        // Write to a temporary file first so a crash never leaves a truncated history
+        let tmp = path.with_extension("json.tmp");
+        if fs::write(&tmp, json).is_ok() {
+            let _ = fs::rename(&tmp, path);
+        }
+    }
+}
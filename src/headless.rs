@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::Config;
+use crate::feed::Feed;
+use crate::snapshot::Snapshot;
+use crate::state::{AppState, Role};
+
+/// Fetch once, print the snapshot and exit (non-zero on fetch failure)
+pub async fn run_snapshot(config: Config, config_path: Option<PathBuf>, json: bool) -> Result<()> {
+    // One-shot: nothing is run, published, switched or persisted
+    let mut app = AppState::new(config, config_path, Role::Observer);
+
+    app.update()
+        .await
+        .context("Failed to fetch data from Home Assistant")?;
+
+    let snapshot = Snapshot::latest(&app).context("No data available after fetch")?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&snapshot)?);
+    } else {
+        print!("{}", snapshot.to_text());
+    }
+
+    Ok(())
+}
+
+/// Output format for `emon stream`
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum StreamFormat {
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// Print one snapshot line per fetch until interrupted or stdout is closed
+pub async fn run_stream(
+    config: Config,
+    config_path: Option<PathBuf>,
+    format: StreamFormat,
+    listen: Option<String>,
+) -> Result<()> {
+    let feed = Feed::new();
+    feed.start_servers(&config, listen.as_deref()).await?;
+
+    // A stream is side-effect free unless it also serves attached clients as the collector
+    let role = if listen.is_some() {
+        Role::Owner
+    } else {
+        Role::Observer
+    };
+    let mut app = AppState::new(config, config_path, role);
+
+    let mut ticker = tokio::time::interval(app.config.home_assistant.get_fetch_interval());
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = &mut shutdown => break,
+        }
+
+        let result = app.update().await;
+        if let Err(e) = &result {
+            app.error = Some(e.to_string());
+        }
+        feed.publish(&app);
+        for error in app.publish_failures() {
+            eprintln!("emon: publish failed: {}", error);
+        }
+
+        if let Err(e) = result {
+            // Keep stdout clean for consumers; report fetch errors on stderr and retry next tick
+            eprintln!("emon: fetch failed: {:#}", e);
+            continue;
+        }
+
+        let Some(snapshot) = Snapshot::latest(&app) else {
+            continue;
+        };
+
+        let line = match format {
+            StreamFormat::Ndjson => serde_json::to_string(&snapshot)?,
+        };
+
+        // Exit quietly when the reader goes away (e.g. `emon stream | head`)
+        let mut stdout = io::stdout().lock();
+        match writeln!(stdout, "{}", line).and_then(|_| stdout.flush()) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve on Ctrl-C, or on SIGTERM where supported
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
@@ -0,0 +1,293 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::state::PowerData;
+
+/// How often the battery ledger is written to disk (it is also written when the day changes)
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Days shown in the Battery page trend and averaged for round-trip efficiency
+const TREND_DAYS: usize = 14;
+
+/// Capacity estimates averaged for state of health
+const CAPACITY_RUNS: usize = 5;
+
+/// A charge-to-float run must cover at least this much SoC to estimate capacity
+const MIN_RUN_SOC: f64 = 40.0;
+
+/// Gaps longer than this are not integrated
+const MAX_GAP: Duration = Duration::from_secs(300);
+
+/// One day of battery usage, keyed by local date in the ledger
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BatteryDay {
+    pub charge_kwh: f64,
+    pub discharge_kwh: f64,
+    #[serde(default)]
+    pub min_soc: Option<f64>, // None without an SoC sensor or estimate
+    #[serde(default)]
+    pub max_soc: Option<f64>,
+    #[serde(default)]
+    pub capacity_kwh: Option<f64>, // Usable capacity from the last charge-to-float run of the day
+}
+
+impl BatteryDay {
+    /// Round-trip efficiency in percent; None without enough charge to compare against
+    fn efficiency(charge_kwh: f64, discharge_kwh: f64) -> Option<f64> {
+        (charge_kwh > 0.1).then(|| discharge_kwh / charge_kwh * 100.0)
+    }
+}
+
+/// One row of the Battery page trend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryTrendDay {
+    pub date: String,
+    pub charge_kwh: f64,
+    pub discharge_kwh: f64,
+    pub cycles: f64,
+    pub depth_of_discharge: Option<f64>, // Percent, 100 - lowest SoC of the day
+    pub efficiency: Option<f64>,
+    pub capacity_kwh: Option<f64>,
+}
+
+/// Battery wear from the persisted ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryHealth {
+    pub cycles: f64, // Equivalent full cycles since tracking started
+    pub tracked_days: usize,
+    pub depth_of_discharge: Option<f64>,  // Today, percent
+    pub efficiency: Option<f64>,          // Round-trip over the trend window, percent
+    pub usable_capacity_kwh: Option<f64>, // Average of recent charge-to-float runs
+    pub state_of_health: Option<f64>,     // Usable vs. rated capacity, percent
+    pub trend: Vec<BatteryTrendDay>,      // Most recent last
+}
+
+impl BatteryHealth {
+    pub fn numeric_fields(&self) -> Vec<(&'static str, f64)> {
+        let optional = [
+            ("battery_depth_of_discharge", self.depth_of_discharge),
+            ("battery_round_trip_efficiency", self.efficiency),
+            ("battery_usable_capacity_kwh", self.usable_capacity_kwh),
+            ("battery_state_of_health", self.state_of_health),
+        ];
+        let mut fields = vec![("battery_cycles", self.cycles)];
+        fields.extend(
+            optional
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, value?))),
+        );
+        fields
+    }
+}
+
+/// Charging since the lowest SoC seen after the last float
+#[derive(Debug)]
+struct ChargeRun {
+    start_soc: f64,
+    kwh: f64, // Net energy into the battery
+}
+
+/// Tracks cycles, depth of discharge, efficiency and usable capacity in ~/.emon/battery_health.json
+#[derive(Debug)]
+pub struct HealthTracker {
+    capacity_kwh: f64,
+    ledger: BTreeMap<String, BatteryDay>, // Keyed by local date, YYYY-MM-DD
+    run: Option<ChargeRun>,
+    last: Option<(Instant, f64)>, // Previous (time, battery power in W)
+    last_day: Option<String>,
+    path: Option<PathBuf>,
+    last_save: Instant,
+}
+
+impl HealthTracker {
+    /// The ledger is read-only without `persist`
+    pub fn new(capacity_kwh: f64, persist: bool) -> Self {
+        let path = config::get_config_dir()
+            .ok()
+            .map(|dir| dir.join("battery_health.json"));
+        let ledger = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            capacity_kwh,
+            ledger,
+            run: None,
+            last: None,
+            last_day: None,
+            path: path.filter(|_| persist),
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Record today's battery counters and attach the health summary
+    ///
+    /// `soc` is None when neither an SoC sensor nor the estimator provides one; `floating`
+    /// comes from `is_battery_floating`.
+    pub fn apply(
+        &mut self,
+        data: &mut PowerData,
+        today: NaiveDate,
+        soc: Option<f64>,
+        floating: bool,
+    ) {
+        let key = today.format("%Y-%m-%d").to_string();
+        if self.last_day.as_ref().is_some_and(|day| *day != key) {
+            self.save();
+        }
+        self.last_day = Some(key.clone());
+
+        let day = self.ledger.entry(key.clone()).or_insert(BatteryDay {
+            charge_kwh: 0.0,
+            discharge_kwh: 0.0,
+            min_soc: None,
+            max_soc: None,
+            capacity_kwh: None,
+        });
+        // Daily counters reset at midnight, so the highest value of the day is its total. A lower
+        // reading (an integrated counter after a restart) or an unavailable one must not erase it.
+        if data.is_available("day_battery_charge") {
+            day.charge_kwh = day.charge_kwh.max(data.day_battery_charge);
+        }
+        if data.is_available("day_battery_discharge") {
+            day.discharge_kwh = day.discharge_kwh.max(data.day_battery_discharge);
+        }
+        if let Some(soc) = soc {
+            day.min_soc = Some(day.min_soc.map_or(soc, |min| min.min(soc)));
+            day.max_soc = Some(day.max_soc.map_or(soc, |max| max.max(soc)));
+        }
+
+        // An unavailable battery power reads 0, so the run would miss that energy
+        if !data.is_available("battery_power") {
+            self.last = None;
+            self.run = None;
+            return self.finish(data, &key);
+        }
+
+        // Capacity from net energy charged between the lowest SoC and float
+        let kwh = match self.last {
+            Some((at, previous)) => {
+                let dt = data.timestamp.saturating_duration_since(at);
+                if dt <= MAX_GAP {
+                    (previous + data.battery_power) / 2.0 * dt.as_secs_f64() / 3600.0 / 1000.0
+                } else {
+                    self.run = None; // Missed part of the run
+                    0.0
+                }
+            }
+            None => 0.0,
+        };
+        self.last = Some((data.timestamp, data.battery_power));
+
+        let Some(soc) = soc else {
+            self.run = None;
+            return self.finish(data, &key);
+        };
+        if floating {
+            if let Some(run) = self.run.take() {
+                let soc_delta = soc - run.start_soc;
+                // An estimated SoC is re-anchored at float and says nothing about capacity
+                if soc_delta >= MIN_RUN_SOC && !data.battery_soc_estimated {
+                    day.capacity_kwh = Some(run.kwh / (soc_delta / 100.0));
+                }
+            }
+        } else {
+            match &mut self.run {
+                Some(run) if soc >= run.start_soc => run.kwh += kwh,
+                _ => {
+                    self.run = Some(ChargeRun {
+                        start_soc: soc,
+                        kwh: 0.0,
+                    })
+                }
+            }
+        }
+
+        self.finish(data, &key);
+    }
+
+    fn finish(&mut self, data: &mut PowerData, today: &str) {
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+        data.battery_health = Some(self.summary(today));
+    }
+
+    fn summary(&self, today: &str) -> BatteryHealth {
+        let cycles_for = |discharge_kwh: f64| discharge_kwh / self.capacity_kwh.max(f64::EPSILON);
+        let trend: Vec<BatteryTrendDay> = self
+            .ledger
+            .iter()
+            .rev()
+            .take(TREND_DAYS)
+            .rev()
+            .map(|(date, day)| BatteryTrendDay {
+                date: date.clone(),
+                charge_kwh: day.charge_kwh,
+                discharge_kwh: day.discharge_kwh,
+                cycles: cycles_for(day.discharge_kwh),
+                depth_of_discharge: day.min_soc.map(|soc| 100.0 - soc),
+                efficiency: BatteryDay::efficiency(day.charge_kwh, day.discharge_kwh),
+                capacity_kwh: day.capacity_kwh,
+            })
+            .collect();
+
+        let discharge: f64 = self.ledger.values().map(|d| d.discharge_kwh).sum();
+        let (window_charge, window_discharge) = trend.iter().fold((0.0, 0.0), |(c, d), day| {
+            (c + day.charge_kwh, d + day.discharge_kwh)
+        });
+
+        let capacities: Vec<f64> = self
+            .ledger
+            .values()
+            .rev()
+            .filter_map(|d| d.capacity_kwh)
+            .take(CAPACITY_RUNS)
+            .collect();
+        let usable_capacity_kwh = (!capacities.is_empty())
+            .then(|| capacities.iter().sum::<f64>() / capacities.len() as f64);
+
+        BatteryHealth {
+            cycles: cycles_for(discharge),
+            tracked_days: self.ledger.len(),
+            depth_of_discharge: self
+                .ledger
+                .get(today)
+                .and_then(|d| d.min_soc)
+                .map(|soc| 100.0 - soc),
+            efficiency: BatteryDay::efficiency(window_charge, window_discharge),
+            usable_capacity_kwh,
+            state_of_health: usable_capacity_kwh.map(|kwh| kwh / self.capacity_kwh * 100.0),
+            trend,
+        }
+    }
+
+    fn save(&mut self) {
+        self.last_save = Instant::now();
+        let Some(path) = &self.path else {
+            return;
+        };
+        let Ok(json) = serde_json::to_string_pretty(&self.ledger) else {
+            return;
+        };
+
+        // Write to a temporary file first so a crash never leaves a truncated ledger
+        let tmp = path.with_extension("json.tmp");
+        if fs::write(&tmp, json).is_ok() {
+            let _ = fs::rename(&tmp, path);
+        }
+    }
+}
+
+impl Drop for HealthTracker {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
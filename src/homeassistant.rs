@@ -1,15 +1,29 @@
 use anyhow::{Context, Result};
 use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct EntityState {
-    #[allow(dead_code)]
     pub entity_id: String,
     pub state: String,
     #[serde(default)]
-    #[allow(dead_code)]
     pub attributes: serde_json::Value,
+    #[serde(default)]
+    pub last_changed: Option<String>, // RFC 3339
+    #[serde(default)]
+    pub last_updated: Option<String>,
+}
+
+/// Outcome of the latest fetch of one entity, for the inspector
+#[derive(Debug, Clone)]
+pub struct FetchedEntity {
+    pub result: Result<EntityState, String>,
+    pub latency: Duration,
+    pub fetched_at: Instant,
 }
 
 #[derive(Debug)]
@@ -17,6 +31,18 @@ pub struct HomeAssistant {
     url: String,
     token: String,
     client: reqwest::Client,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_micros: AtomicU64,
+    fetched: Mutex<HashMap<String, FetchedEntity>>,
+}
+
+/// Cumulative request counters since startup
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchStats {
+    pub requests: u64,
+    pub errors: u64,
+    pub latency_seconds_total: f64,
 }
 
 impl HomeAssistant {
@@ -26,10 +52,80 @@ impl HomeAssistant {
             .build()
             .unwrap();
 
-        Self { url, token, client }
+        Self {
+            url,
+            token,
+            client,
+            requests: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            latency_micros: AtomicU64::new(0),
+            fetched: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn stats(&self) -> FetchStats {
+        FetchStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            latency_seconds_total: self.latency_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+        }
     }
 
     pub async fn get_state(&self, entity_id: &str) -> Result<EntityState> {
+        let started = Instant::now();
+        let result = self.fetch_state(entity_id).await;
+
+        let latency = started.elapsed();
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.latency_micros
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+        if result.is_err() {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if let Ok(mut fetched) = self.fetched.lock() {
+            let entry = FetchedEntity {
+                result: match &result {
+                    Ok(state) => Ok(state.clone()),
+                    Err(e) => Err(format!("{:#}", e)),
+                },
+                latency,
+                fetched_at: Instant::now(),
+            };
+            fetched.insert(entity_id.to_string(), entry);
+        }
+
+        result
+    }
+
+    /// Latest fetch of `entity_id` through `get_state`, if any
+    pub fn fetched(&self, entity_id: &str) -> Option<FetchedEntity> {
+        self.fetched.lock().ok()?.get(entity_id).cloned()
+    }
+
+    /// Every entity Home Assistant knows about (`GET /api/states`)
+    pub async fn get_all_states(&self) -> Result<Vec<EntityState>> {
+        let url = format!("{}/api/states", self.url.trim_end_matches('/'));
+        let response = self
+            .client
+            .get(&url)
+            .header(AUTHORIZATION, format!("Bearer {}", self.token))
+            .header(ACCEPT, "application/json")
+            .send()
+            .await
+            .context("Failed to fetch all states")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Home Assistant API returned status {}", response.status());
+        }
+
+        response
+            .json()
+            .await
+            .context("Failed to parse the list of states")
+    }
+
+    async fn fetch_state(&self, entity_id: &str) -> Result<EntityState> {
         let url = format!(
             "{}/api/states/{}",
             self.url.trim_end_matches('/'),
@@ -61,4 +157,61 @@ impl HomeAssistant {
 
         Ok(state)
     }
+
+    /// Create or update an entity's state (`POST /api/states/<entity_id>`)
+    pub async fn set_state(
+        &self,
+        entity_id: &str,
+        state: &str,
+        attributes: serde_json::Value,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/api/states/{}",
+            self.url.trim_end_matches('/'),
+            entity_id
+        );
+        let body = serde_json::json!({ "state": state, "attributes": attributes });
+        self.post(&url, &body)
+            .await
+            .with_context(|| format!("Failed to set state of {}", entity_id))?;
+        Ok(())
+    }
+
+    /// Call a service (`POST /api/services/<domain>/<service>`), returning the response JSON
+    pub async fn call_service(
+        &self,
+        domain: &str,
+        service: &str,
+        data: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let url = format!(
+            "{}/api/services/{}/{}",
+            self.url.trim_end_matches('/'),
+            domain,
+            service
+        );
+        self.post(&url, data)
+            .await
+            .with_context(|| format!("Failed to call service {}.{}", domain, service))
+    }
+
+    async fn post(&self, url: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        let response = self
+            .client
+            .post(url)
+            .header(AUTHORIZATION, format!("Bearer {}", self.token))
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, "application/json")
+            .json(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Home Assistant API returned status {}", response.status());
+        }
+
+        // Some endpoints answer with an empty body
+        let text = response.text().await?;
+        Ok(serde_json::from_str(&text).unwrap_or(serde_json::Value::Null))
+    }
 }
@@ -0,0 +1,118 @@
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Largest request head we are willing to buffer
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Pause after a failed `accept` so a persistent error (e.g. out of file descriptors) does not spin
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Minimal parsed HTTP request line
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+}
+
+/// Listeners served by `accept`
+pub trait Listener {
+    type Stream;
+
+    fn accept_stream(&self) -> impl Future<Output = std::io::Result<Self::Stream>> + Send;
+}
+
+impl Listener for TcpListener {
+    type Stream = TcpStream;
+
+    async fn accept_stream(&self) -> std::io::Result<TcpStream> {
+        self.accept().await.map(|(stream, _)| stream)
+    }
+}
+
+#[cfg(unix)]
+impl Listener for tokio::net::UnixListener {
+    type Stream = tokio::net::UnixStream;
+
+    async fn accept_stream(&self) -> std::io::Result<tokio::net::UnixStream> {
+        self.accept().await.map(|(stream, _)| stream)
+    }
+}
+
+/// Wait for the next connection, backing off on errors; only the first error of a run is logged
+pub async fn accept<L: Listener>(listener: &L, server: &str) -> L::Stream {
+    let mut failing = false;
+    loop {
+        match listener.accept_stream().await {
+            Ok(stream) => return stream,
+            Err(e) => {
+                if !failing {
+                    eprintln!("emon: {} failed to accept a connection: {}", server, e);
+                    failing = true;
+                }
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+            }
+        }
+    }
+}
+
+/// Read the request head and parse the request line
+pub async fn read_request(stream: &mut TcpStream) -> Result<Request> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if buf.len() > MAX_REQUEST_BYTES {
+            anyhow::bail!("Request head too large");
+        }
+    }
+
+    let head = String::from_utf8_lossy(&buf);
+    let mut parts = head
+        .lines()
+        .next()
+        .context("Empty request")?
+        .split_whitespace();
+    let method = parts.next().context("Missing method")?.to_string();
+    let target = parts.next().context("Missing path")?;
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    Ok(Request { method, path })
+}
+
+/// Write a complete response and close the connection
+pub async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Write response headers for a body that is streamed afterwards (e.g. SSE)
+pub async fn write_stream_head(stream: &mut TcpStream, content_type: &str) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+        content_type
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.flush().await?;
+    Ok(())
+}
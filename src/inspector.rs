@@ -0,0 +1,120 @@
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
+use crate::config::Entities;
+use crate::homeassistant::{EntityState, HomeAssistant};
+
+/// Inspector tabs, switched with Tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InspectorTab {
+    #[default]
+    Configured,
+    All,
+}
+
+/// Raw entity inspector, opened with `i`
+#[derive(Debug, Default)]
+pub struct Inspector {
+    pub tab: InspectorTab,
+    pub query: String,
+    pub searching: bool, // Typing into the search box
+    pub selected: usize,
+    pub scroll: u16,                                   // Attributes pane, in lines
+    pub all: Option<Result<Vec<EntityState>, String>>, // `/api/states`, loaded on first use of the tab
+}
+
+/// One entity in the inspector list, borrowing `/api/states` rather than copying it on every redraw
+#[derive(Debug, Clone)]
+pub struct InspectorRow<'a> {
+    pub setting: Option<&'static str>, // Config key, on the Configured tab
+    pub entity_id: &'a str,
+    pub state: Option<Cow<'a, EntityState>>,
+    pub error: Option<String>,
+    pub latency: Option<Duration>, // Of the latest fetch by emon
+    pub fetched_at: Option<Instant>,
+}
+
+impl Inspector {
+    /// Rows of the current tab matching the search
+    pub fn rows<'a>(&'a self, entities: &'a Entities, ha: &HomeAssistant) -> Vec<InspectorRow<'a>> {
+        let rows: Vec<InspectorRow> = match self.tab {
+            InspectorTab::Configured => entities
+                .configured()
+                .into_iter()
+                .map(|(setting, entity_id)| {
+                    let fetched = ha.fetched(entity_id);
+                    let (state, error) = match fetched.as_ref().map(|f| &f.result) {
+                        Some(Ok(state)) => (Some(Cow::Owned(state.clone())), None),
+                        Some(Err(e)) => (None, Some(e.clone())),
+                        None => (None, None),
+                    };
+                    InspectorRow {
+                        setting: Some(setting),
+                        entity_id,
+                        state,
+                        error,
+                        latency: fetched.as_ref().map(|f| f.latency),
+                        fetched_at: fetched.map(|f| f.fetched_at),
+                    }
+                })
+                .collect(),
+            InspectorTab::All => match &self.all {
+                Some(Ok(states)) => states
+                    .iter()
+                    .map(|state| InspectorRow {
+                        setting: None,
+                        entity_id: &state.entity_id,
+                        state: Some(Cow::Borrowed(state)),
+                        error: None,
+                        latency: None,
+                        fetched_at: None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            },
+        };
+
+        let query = self.query.to_lowercase();
+        rows.into_iter()
+            .filter(|row| query.is_empty() || row.matches(&query))
+            .collect()
+    }
+
+    /// Move the selection by `delta` rows out of `count`, resetting the attributes scroll
+    pub fn move_selection(&mut self, delta: isize, count: usize) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(count.saturating_sub(1));
+        self.scroll = 0;
+    }
+
+    pub fn switch_tab(&mut self) {
+        self.tab = match self.tab {
+            InspectorTab::Configured => InspectorTab::All,
+            InspectorTab::All => InspectorTab::Configured,
+        };
+        self.selected = 0;
+        self.scroll = 0;
+    }
+}
+
+impl InspectorRow<'_> {
+    /// Case-insensitive match on entity id, config key, friendly name and state
+    fn matches(&self, query: &str) -> bool {
+        let friendly_name = self
+            .state
+            .as_ref()
+            .and_then(|s| s.attributes.get("friendly_name"))
+            .and_then(|n| n.as_str());
+        [
+            Some(self.entity_id),
+            self.setting,
+            friendly_name,
+            self.state.as_ref().map(|s| s.state.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|text| text.to_lowercase().contains(query))
+    }
+}
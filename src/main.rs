@@ -1,17 +1,36 @@
+mod alerts;
+mod battery;
+mod collector;
 mod config;
+mod daemon;
+mod energy;
+mod exporter;
+mod feed;
+mod forecast;
+mod headless;
+mod health;
 mod homeassistant;
+mod http;
+mod inspector;
+mod publish;
+mod roi;
+mod snapshot;
 mod state;
+mod sun;
+mod surplus;
+mod tariff;
 mod ui;
+mod web;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 use crossterm::{
     event::{self, Event, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
+use std::io::{self, Write};
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -35,40 +54,135 @@ struct Args {
 
     #[arg(short = 'v', long, action = clap::ArgAction::Version, help = "Print version information")]
     version: (),
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "ADDR",
+        help = "Publish snapshots to attached clients from the TUI, stream or daemon (host:port or unix:/path.sock)"
+    )]
+    listen: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDR",
+        conflicts_with = "listen",
+        help = "TUI only: render from a collector started with --listen instead of polling Home Assistant"
+    )]
+    attach: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Fetch once, print all metrics and exit (non-zero exit code on fetch failure)
+    Snapshot {
+        #[arg(long, help = "Print as JSON instead of key = value lines")]
+        json: bool,
+    },
+    /// Print one line per fetch to stdout for piping into other tools
+    Stream {
+        #[arg(long, value_enum, default_value_t = headless::StreamFormat::Ndjson)]
+        format: headless::StreamFormat,
+    },
+    /// Run the fetch loop without a terminal, logging structured events
+    Daemon {
+        #[arg(long, help = "Append JSON log lines to this file instead of stdout")]
+        log_file: Option<PathBuf>,
+        #[arg(long, help = "Also log every successful fetch")]
+        verbose: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    // clap can't declare a conflict between an argument and a subcommand
+    match &args.command {
+        Some(Command::Snapshot { .. }) if args.listen.is_some() => Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--listen cannot be used with snapshot, which exits after one fetch",
+            )
+            .exit(),
+        Some(_) if args.attach.is_some() => Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--attach only applies to the TUI and cannot be used with a subcommand",
+            )
+            .exit(),
+        _ => {}
+    }
 
     let config = config::load_config_at(args.config.as_deref())?;
     let config_path = args.config.clone();
 
+    match args.command {
+        Some(Command::Snapshot { json }) => headless::run_snapshot(config, config_path, json).await,
+        Some(Command::Stream { format }) => {
+            headless::run_stream(config, config_path, format, args.listen).await
+        }
+        Some(Command::Daemon { log_file, verbose }) => {
+            daemon::run_daemon(config, config_path, log_file, verbose, args.listen).await
+        }
+        None => run_tui(config, config_path, args.listen, args.attach).await,
+    }
+}
+
+async fn run_tui(
+    config: config::Config,
+    config_path: Option<PathBuf>,
+    listen: Option<String>,
+    attach: Option<String>,
+) -> Result<()> {
+    // Bind background servers before taking over the terminal so errors stay readable
+    let feed = feed::Feed::new();
+    feed.start_servers(&config, listen.as_deref()).await?;
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = state::AppState::new(config, config_path);
+    let role = if attach.is_some() {
+        state::Role::Attached
+    } else {
+        state::Role::Owner
+    };
+    let mut app = state::AppState::new(config, config_path, role);
+
+    // In attach mode the collector does the polling; we only render what it sends
+    let mut collector_rx = attach.clone().map(collector::attach);
+    app.attached_to = attach;
 
     // Fetch data immediately on startup
-    let _ = app.update().await;
+    if collector_rx.is_none() {
+        let _ = app.update().await;
+        feed.publish(&app);
+    }
 
     // Determine fetch interval from config
-    // 0 = realtime (~100ms), None = default 5s, Some(n) = n seconds
-    let interval_ms = match app.config.home_assistant.fetch_interval_seconds {
-        Some(0) => 100,      // Realtime mode
-        Some(s) => s * 1000, // User-defined seconds to milliseconds
-        None => 5000,        // Default 5 seconds
-    };
-    let tick_rate = Duration::from_millis(interval_ms);
+    let tick_rate = app.config.home_assistant.get_fetch_interval();
     let mut last_tick = std::time::Instant::now();
 
     // UI refresh rate for counter updates (100ms for smooth counting)
     let ui_refresh_rate = Duration::from_millis(100);
 
+    let mut alert_watcher = alerts::AlertWatcher::default();
+
     loop {
+        // Bell and desktop notifications for newly raised alerts
+        let (raised, _) = alert_watcher.changes(&app.alert_list);
+        let actions: String = raised.iter().map(alerts::terminal_actions).collect();
+        if !actions.is_empty() {
+            let backend = terminal.backend_mut();
+            write!(backend, "{}", actions)?;
+            backend.flush()?;
+        }
+
         terminal.draw(|f| ui::render(f, &app))?;
 
         // Use shorter timeout to ensure UI updates frequently for counter
@@ -79,21 +193,73 @@ async fn main() -> Result<()> {
 
         if event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char('q') {
-                    break;
+                let had_error = app.error.is_some();
+                match key.code {
+                    code if app.action_menu.is_some() => {
+                        if let Some(index) = handle_action_menu_key(&mut app, code) {
+                            let name = app.config.actions[index].name.clone();
+                            app.set_status(format!("Running {}...", name), true);
+                            terminal.draw(|f| ui::render(f, &app))?;
+                            app.run_action(index).await;
+                        }
+                    }
+                    code if app.inspector.is_some() => {
+                        let reload = handle_inspector_key(&mut app, code);
+                        if reload {
+                            app.load_all_entities().await;
+                        }
+                    }
+                    KeyCode::Char('q') => break,
+                    KeyCode::Char('x') => app.open_action_menu(),
+                    KeyCode::Char('i') => app.inspector = Some(inspector::Inspector::default()),
+                    KeyCode::Tab => app.page = app.page.next(),
+                    KeyCode::BackTab => app.page = app.page.prev(),
+                    KeyCode::Up | KeyCode::Char('k') if app.page == state::Page::Alerts => {
+                        app.move_alert_selection(-1)
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if app.page == state::Page::Alerts => {
+                        app.move_alert_selection(1)
+                    }
+                    KeyCode::Char('a') if app.page == state::Page::Alerts => {
+                        app.acknowledge_selected_alert()
+                    }
+                    KeyCode::Char('s') if app.page == state::Page::Alerts => {
+                        app.snooze_selected_alert()
+                    }
+                    KeyCode::Char('u') if app.page == state::Page::Alerts => {
+                        app.unsnooze_selected_alert()
+                    }
+                    KeyCode::Char(c) => {
+                        if let Some(page) = c.to_digit(10).and_then(state::Page::from_number) {
+                            app.page = page;
+                        }
+                    }
+                    _ => {}
                 }
                 // Dismiss error on any other key press
-                if app.error.is_some() {
+                if had_error {
                     app.error = None;
                 }
             }
         }
 
-        if last_tick.elapsed() >= tick_rate {
+        if let Some(rx) = collector_rx.as_mut() {
+            let mut received = false;
+            while let Ok(message) = rx.try_recv() {
+                collector::apply(&mut app, message);
+                received = true;
+            }
+            if received {
+                feed.publish(&app);
+            }
+        } else if last_tick.elapsed() >= tick_rate {
             match app.update().await {
                 Ok(_) => {
                     // Clear any previous errors on successful update
                     app.error = None;
+                    if let Some(e) = app.publish_failures().pop() {
+                        app.set_status(format!("Publish failed: {}", e), false);
+                    }
                 }
                 Err(e) => {
                     // Auto-reconnect: Try again immediately on error
@@ -101,6 +267,7 @@ async fn main() -> Result<()> {
                     app.error = Some(e.to_string());
                 }
             }
+            feed.publish(&app);
             last_tick = std::time::Instant::now();
         }
     }
@@ -111,3 +278,81 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Keys while the actions menu is open; returns the action to run once confirmed
+fn handle_action_menu_key(app: &mut state::AppState, code: KeyCode) -> Option<usize> {
+    let menu = app.action_menu?;
+    if menu.confirming {
+        match code {
+            KeyCode::Enter | KeyCode::Char('y') => {
+                app.action_menu = None;
+                return Some(menu.selected);
+            }
+            _ => {
+                // Anything else cancels, back to the list
+                app.action_menu = Some(state::ActionMenu {
+                    confirming: false,
+                    ..menu
+                });
+            }
+        }
+        return None;
+    }
+    match code {
+        KeyCode::Up | KeyCode::Char('k') => app.move_action_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_action_selection(1),
+        KeyCode::Enter => {
+            app.action_menu = Some(state::ActionMenu {
+                confirming: true,
+                ..menu
+            })
+        }
+        KeyCode::Esc | KeyCode::Char('x') | KeyCode::Char('q') => app.action_menu = None,
+        _ => {}
+    }
+    None
+}
+
+/// Keys while the inspector is open; returns true when `/api/states` should be (re)loaded
+fn handle_inspector_key(app: &mut state::AppState, code: KeyCode) -> bool {
+    let count = app.inspector.as_ref().map_or(0, |inspector| {
+        inspector
+            .rows(&app.config.home_assistant.entities, &app.ha)
+            .len()
+    });
+    let Some(inspector) = app.inspector.as_mut() else {
+        return false;
+    };
+
+    if inspector.searching {
+        match code {
+            KeyCode::Char(c) => inspector.query.push(c),
+            KeyCode::Backspace => {
+                inspector.query.pop();
+            }
+            KeyCode::Enter | KeyCode::Esc => inspector.searching = false,
+            _ => {}
+        }
+        inspector.selected = 0;
+        inspector.scroll = 0;
+        return false;
+    }
+
+    match code {
+        KeyCode::Esc | KeyCode::Char('i') | KeyCode::Char('q') => app.inspector = None,
+        KeyCode::Char('/') => inspector.searching = true,
+        KeyCode::Tab | KeyCode::BackTab => {
+            inspector.switch_tab();
+            return inspector.tab == inspector::InspectorTab::All && inspector.all.is_none();
+        }
+        KeyCode::Char('r') if inspector.tab == inspector::InspectorTab::All => return true,
+        KeyCode::Up | KeyCode::Char('k') => inspector.move_selection(-1, count),
+        KeyCode::Down | KeyCode::Char('j') => inspector.move_selection(1, count),
+        KeyCode::PageUp => inspector.move_selection(-10, count),
+        KeyCode::PageDown => inspector.move_selection(10, count),
+        KeyCode::Char('K') => inspector.scroll = inspector.scroll.saturating_sub(5),
+        KeyCode::Char('J') => inspector.scroll = inspector.scroll.saturating_add(5),
+        _ => {}
+    }
+    false
+}
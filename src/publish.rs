@@ -0,0 +1,262 @@
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+use crate::alerts::{Alert, AlertWatcher};
+use crate::config::PublishConfig;
+use crate::homeassistant::HomeAssistant;
+use crate::state::PowerData;
+
+/// One call to Home Assistant, queued for the background sender
+#[derive(Debug)]
+enum Request {
+    Notification {
+        service: &'static str,
+        payload: Value,
+    },
+    State {
+        entity_id: String,
+        state: String,
+        attributes: Value,
+    },
+}
+
+/// Pushes derived values and alerts back into Home Assistant (`[publish]` section)
+///
+/// Requests are sent by a background task so a slow or unreachable Home Assistant never
+/// delays fetching or redrawing.
+#[derive(Debug)]
+pub struct Publisher {
+    config: PublishConfig,
+    last: Option<Instant>,
+    watcher: AlertWatcher,
+    ha: Arc<HomeAssistant>,
+    sender: Option<mpsc::UnboundedSender<Vec<Request>>>,
+    /// True while a round of entity states is still being sent
+    sending_states: Arc<AtomicBool>,
+    failures: Arc<Mutex<Vec<String>>>,
+}
+
+impl Publisher {
+    pub fn new(config: PublishConfig, ha: HomeAssistant) -> Self {
+        Self {
+            config,
+            last: None,
+            watcher: AlertWatcher::default(),
+            ha: Arc::new(ha),
+            sender: None,
+            sending_states: Arc::new(AtomicBool::new(false)),
+            failures: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Errors from requests sent since the last call, oldest first
+    pub fn take_failures(&mut self) -> Vec<String> {
+        self.failures
+            .lock()
+            .map(|mut failures| std::mem::take(&mut *failures))
+            .unwrap_or_default()
+    }
+
+    /// Queue alert changes as persistent notifications and, once per interval, the
+    /// configured metrics as entity states. Failed states are retried on the next round.
+    pub fn publish(&mut self, data: &PowerData, alerts: &[Alert]) {
+        let mut requests = Vec::new();
+        if self.config.notifications {
+            let (raised, cleared) = self.watcher.changes(alerts);
+            for alert in raised.iter().filter(|a| !a.is_silenced()) {
+                let message = format!(
+                    "{} is {:.1} ({} {:.1}) since {}",
+                    alert.metric,
+                    alert.value,
+                    alert.comparison.symbol(),
+                    alert.threshold,
+                    alert.raised_at
+                );
+                let payload = json!({
+                    "notification_id": notification_id(alert),
+                    "title": format!("emon {:?}: {}", alert.severity, alert.name),
+                    "message": message,
+                });
+                requests.push(Request::Notification {
+                    service: "create",
+                    payload,
+                });
+            }
+            for alert in &cleared {
+                let payload = json!({ "notification_id": notification_id(alert) });
+                requests.push(Request::Notification {
+                    service: "dismiss",
+                    payload,
+                });
+            }
+        }
+
+        // Skip a round rather than queue it behind one Home Assistant has not taken yet
+        let due = self
+            .last
+            .is_none_or(|at| at.elapsed() >= self.config.get_interval());
+        if due && !self.sending_states.load(Ordering::Relaxed) {
+            self.last = Some(Instant::now());
+            self.sending_states.store(true, Ordering::Relaxed);
+            self.push_states(data, &mut requests);
+        }
+
+        if !requests.is_empty() {
+            self.send(requests);
+        }
+    }
+
+    fn push_states(&self, data: &PowerData, requests: &mut Vec<Request>) {
+        let fields = data.numeric_fields();
+        for metric in &self.config.metrics {
+            // Metrics without a value right now (e.g. time to empty while charging) read unknown
+            let state = fields
+                .iter()
+                .find(|(name, _)| name == metric)
+                .map(|(_, value)| ((value * 100.0).round() / 100.0).to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            let mut attributes = json!({
+                "friendly_name": format!("emon {}", metric.replace('_', " ")),
+                "state_class": state_class_for(metric),
+            });
+            if let Some(unit) = unit_for(metric, data) {
+                attributes["unit_of_measurement"] = unit.into();
+            }
+            requests.push(Request::State {
+                entity_id: format!("{}{}", self.config.prefix, metric),
+                state,
+                attributes,
+            });
+        }
+    }
+
+    /// Hand a batch to the sender task, starting it on first use
+    fn send(&mut self, requests: Vec<Request>) {
+        let sender = self.sender.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(run_sender(
+                self.ha.clone(),
+                rx,
+                self.sending_states.clone(),
+                self.failures.clone(),
+            ));
+            tx
+        });
+        let _ = sender.send(requests);
+    }
+}
+
+/// Send queued batches in order, recording failures for the caller to report
+async fn run_sender(
+    ha: Arc<HomeAssistant>,
+    mut rx: mpsc::UnboundedReceiver<Vec<Request>>,
+    sending_states: Arc<AtomicBool>,
+    failures: Arc<Mutex<Vec<String>>>,
+) {
+    while let Some(requests) = rx.recv().await {
+        let has_states = requests.iter().any(|r| matches!(r, Request::State { .. }));
+        for request in requests {
+            let result = match request {
+                Request::Notification { service, payload } => ha
+                    .call_service("persistent_notification", service, &payload)
+                    .await
+                    .map(|_| ()),
+                Request::State {
+                    entity_id,
+                    state,
+                    attributes,
+                } => ha.set_state(&entity_id, &state, attributes).await,
+            };
+            if let Err(e) = result {
+                if let Ok(mut failures) = failures.lock() {
+                    failures.push(format!("{:#}", e));
+                }
+            }
+        }
+        if has_states {
+            sending_states.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// One notification per rule, so a re-raised alert replaces the previous one
+fn notification_id(alert: &Alert) -> String {
+    let slug: String = format!("{}_{:?}", alert.name, alert.severity)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("emon_alert_{}", slug)
+}
+
+/// Energy counters whose names carry no unit suffix
+const ENERGY_COUNTERS: &[&str] = &[
+    "total_pv_generation",
+    "load_energy_yesterday",
+    "load_energy_total",
+    "night_consume",
+    "pv_forecast_today",
+    "pv_forecast_remain",
+];
+
+/// Home Assistant state class: accumulating energy grows until its daily/billing reset,
+/// money totals may also go down (e.g. savings against export credit), the rest are samples
+fn state_class_for(metric: &str) -> &'static str {
+    let period = ["today_", "month_", "year_", "bill_", "roi_"];
+    let money = ["_cost", "_savings", "bill_total"];
+    if money.iter().any(|m| metric.ends_with(m)) && period.iter().any(|p| metric.starts_with(p)) {
+        "total"
+    } else if is_day_energy(metric)
+        || matches!(
+            metric,
+            "today_on_peak_kwh" | "today_off_peak_kwh" | "bill_kwh" | "battery_cycles"
+        )
+        || ENERGY_COUNTERS.contains(&metric)
+    {
+        "total_increasing"
+    } else {
+        "measurement"
+    }
+}
+
+/// `day_*` counters from the inverter (`day_grid_import`, `day_pv_energy`, ...), not the ratios
+fn is_day_energy(metric: &str) -> bool {
+    metric.starts_with("day_") && !metric.contains("self_")
+}
+
+/// Unit of measurement for a numeric field, from its naming convention
+fn unit_for(metric: &str, data: &PowerData) -> Option<String> {
+    let percent = [
+        "self_consumption",
+        "self_sufficiency",
+        "soc",
+        "efficiency",
+        "state_of_health",
+        "depth_of_discharge",
+        "percent",
+    ];
+    let money = ["cost", "savings", "price", "bill_total"];
+    let unit =
+        if metric.ends_with("_kwh") || is_day_energy(metric) || ENERGY_COUNTERS.contains(&metric) {
+            "kWh"
+        } else if metric.ends_with("_seconds") {
+            "s"
+        } else if percent.iter().any(|p| metric.contains(p)) {
+            "%"
+        } else if money.iter().any(|m| metric.contains(m)) {
+            return data.cost.as_ref().map(|c| c.currency.clone());
+        } else if metric.ends_with("_temp") {
+            "°C"
+        } else if matches!(
+            metric,
+            "solar" | "load" | "grid_import" | "grid_export" | "battery_power"
+        ) {
+            "W"
+        } else {
+            return None;
+        };
+    Some(unit.to_string())
+}
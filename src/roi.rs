@@ -0,0 +1,102 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::config::RoiConfig;
+use crate::tariff::DayCost;
+
+/// Ledger days averaged for the savings rate used in the payback projection
+const RATE_WINDOW_DAYS: usize = 30;
+
+/// Progress towards paying back the installation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Payback {
+    pub investment: f64,        // Installation cost plus loan interest
+    pub savings: f64,           // Tracked plus estimated
+    pub tracked_savings: f64,   // From the cost ledger
+    pub estimated_savings: f64, // PV generated before tracking started, valued at the tracked average
+    pub paid_back_percent: f64,
+    pub savings_per_day: f64,
+    pub payback_date: Option<String>, // Projected, YYYY-MM-DD; None once paid back or without savings
+}
+
+/// Total interest paid over an amortized loan
+fn loan_interest(roi: &RoiConfig) -> f64 {
+    let (Some(principal), Some(months)) = (roi.loan_amount, roi.loan_term_months) else {
+        return 0.0;
+    };
+    let n = months as f64;
+    let r = roi.loan_interest_percent.unwrap_or(0.0) / 100.0 / 12.0;
+    if r <= 0.0 || n <= 0.0 {
+        return 0.0;
+    }
+    let payment = principal * r / (1.0 - (1.0 + r).powf(-n));
+    payment * n - principal
+}
+
+/// Compute payback from the cost ledger and the lifetime PV counter (kWh)
+///
+/// `fallback_price` values untracked PV when the ledger has no PV energy yet.
+pub fn payback(
+    roi: &RoiConfig,
+    ledger: &BTreeMap<String, DayCost>,
+    total_pv_kwh: f64,
+    today: NaiveDate,
+    fallback_price: f64,
+) -> Payback {
+    let installed = NaiveDate::parse_from_str(&roi.installation_date, "%Y-%m-%d").unwrap_or(today);
+    let days: Vec<(&String, &DayCost)> = ledger.range(roi.installation_date.clone()..).collect();
+
+    let tracked_savings: f64 = days.iter().map(|(_, d)| d.savings()).sum();
+    let tracked_pv: f64 = days.iter().map(|(_, d)| d.pv_kwh).sum();
+
+    // Lifetime PV not covered by the ledger happened before emon tracked costs
+    let value_per_kwh = if tracked_pv > 1.0 {
+        tracked_savings / tracked_pv
+    } else {
+        fallback_price
+    };
+    let estimated_savings = (total_pv_kwh - tracked_pv).max(0.0) * value_per_kwh;
+    let savings = tracked_savings + estimated_savings;
+
+    let investment = roi.installation_cost + loan_interest(roi);
+    let paid_back_percent = if investment > 0.0 {
+        savings / investment * 100.0
+    } else {
+        100.0
+    };
+
+    // Recent full days reflect the current season best; fall back to the lifetime average
+    let today_key = today.format("%Y-%m-%d").to_string();
+    let recent: Vec<f64> = days
+        .iter()
+        .rev()
+        .filter(|(date, _)| **date != today_key)
+        .take(RATE_WINDOW_DAYS)
+        .map(|(_, d)| d.savings())
+        .collect();
+    let savings_per_day = if !recent.is_empty() {
+        recent.iter().sum::<f64>() / recent.len() as f64
+    } else {
+        let elapsed = (today - installed).num_days().max(1) as f64;
+        savings / elapsed
+    };
+
+    let remaining = investment - savings;
+    let payback_date = if remaining > 0.0 && savings_per_day > 0.0 {
+        let days_left = (remaining / savings_per_day).ceil() as i64;
+        today.checked_add_signed(chrono::Duration::days(days_left))
+    } else {
+        None
+    };
+
+    Payback {
+        investment,
+        savings,
+        tracked_savings,
+        estimated_savings,
+        paid_back_percent,
+        savings_per_day,
+        payback_date: payback_date.map(|d| d.format("%Y-%m-%d").to_string()),
+    }
+}
@@ -0,0 +1,91 @@
+use chrono_tz::Tz;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+
+use crate::alerts;
+use crate::state::{AppState, BatteryState, PowerData, Severity};
+
+/// Serializable view of the latest reading plus derived values
+#[derive(Debug, Serialize)]
+pub struct Snapshot<'a> {
+    /// Wall-clock time of the reading in the configured timezone (RFC 3339)
+    pub timestamp: String,
+    #[serde(flatten, serialize_with = "serialize_readings")]
+    pub data: &'a PowerData,
+    pub battery_state: BatteryState,
+    pub battery_floating: bool,
+    /// Metrics with an active alert and its highest severity, for coloring readings
+    pub severity: BTreeMap<String, Severity>,
+}
+
+/// Serialize the reading with unavailable metrics as `null` rather than the 0 their field holds
+fn serialize_readings<S: Serializer>(data: &&PowerData, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut value = serde_json::to_value(data).map_err(serde::ser::Error::custom)?;
+    if let Some(map) = value.as_object_mut() {
+        for key in data.unavailable.keys() {
+            if let Some(field) = map.get_mut(key) {
+                *field = serde_json::Value::Null;
+            }
+        }
+    }
+    value.serialize(serializer)
+}
+
+impl<'a> Snapshot<'a> {
+    pub fn new(data: &'a PowerData, battery_floating: bool, tz: Tz) -> Self {
+        Self {
+            timestamp: data.fetched_at.with_timezone(&tz).to_rfc3339(),
+            data,
+            battery_state: BatteryState::from_power(data.battery_power, battery_floating),
+            battery_floating,
+            severity: data
+                .active_alerts
+                .iter()
+                .map(|alert| {
+                    (
+                        alert.metric.clone(),
+                        alerts::severity_of(data, &alert.metric),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Build a snapshot from the most recent history entry, if any
+    pub fn latest(app: &'a AppState) -> Option<Self> {
+        let data = app.history.last()?;
+        let battery_floating = app.is_battery_floating(data.battery_voltage, data.battery_power);
+
+        Some(Self::new(
+            data,
+            battery_floating,
+            app.config.home_assistant.get_timezone(),
+        ))
+    }
+
+    /// Render as `key = value` lines for human consumption
+    pub fn to_text(&self) -> String {
+        let value = serde_json::to_value(self).unwrap_or_default();
+        let mut out = String::new();
+
+        if let Some(map) = value.as_object() {
+            for (key, val) in map {
+                if let Some(reason) = self.data.unavailable.get(key) {
+                    let reason = serde_json::to_value(reason).unwrap_or_default();
+                    out.push_str(&format!(
+                        "{} = — ({})\n",
+                        key,
+                        reason.as_str().unwrap_or("")
+                    ));
+                    continue;
+                }
+                match val {
+                    serde_json::Value::String(s) => out.push_str(&format!("{} = {}\n", key, s)),
+                    other => out.push_str(&format!("{} = {}\n", key, other)),
+                }
+            }
+        }
+
+        out
+    }
+}
@@ -1,11 +1,23 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, TryLockError};
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use crate::config::Config;
+use crate::alerts::{ActiveAlert, Alert, AlertEngine};
+use crate::battery::{BatteryEstimator, SocEstimator};
+use crate::config::{self, Config};
+use crate::energy::{self, EnergyIntegrator};
+use crate::forecast::{NightForecaster, OvernightForecast};
+use crate::health::{BatteryHealth, HealthTracker};
 use crate::homeassistant::{EntityState, HomeAssistant};
+use crate::inspector::Inspector;
+use crate::publish::Publisher;
+use crate::surplus::{SurplusAdvice, SurplusAdvisor};
+use crate::tariff::{CostSummary, CostTracker, Tariff};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PowerData {
     // Core power readings
     pub solar: f64,
@@ -76,8 +88,406 @@ pub struct PowerData {
     pub load_energy_total: f64,
     pub day_consume: f64,
 
+    // Derived ratios in percent (see `energy`), filled in after each fetch
+    pub self_consumption: f64,
+    pub self_sufficiency: f64,
+    pub day_self_consumption: f64,
+    pub day_self_sufficiency: f64,
+
+    /// `day_*` fields integrated locally because their sensor is not configured
+    #[serde(default)]
+    pub computed_daily: Vec<String>,
+
+    /// Configured metrics without a usable value in this reading; their field reads 0
+    #[serde(default)]
+    pub unavailable: BTreeMap<String, Availability>,
+
+    // Battery estimates (see `battery`); times are in seconds
+    #[serde(default)]
+    pub battery_soc_estimated: bool, // `battery_soc` comes from the SoC estimator
+    pub battery_power_smoothed: f64,
+    #[serde(default)]
+    pub battery_stored_kwh: Option<f64>,
+    #[serde(default)]
+    pub battery_time_to_full: Option<f64>,
+    #[serde(default)]
+    pub battery_time_to_reserve: Option<f64>,
+
+    /// Battery wear, with `battery_capacity_kwh` configured
+    #[serde(default)]
+    pub battery_health: Option<BatteryHealth>,
+
+    /// Overnight battery forecast, with `latitude`/`longitude` configured
+    #[serde(default)]
+    pub overnight: Option<OvernightForecast>,
+
+    /// Excess solar advice from the `[surplus]` section, if configured
+    #[serde(default)]
+    pub surplus: Option<SurplusAdvice>,
+
+    /// Costs from the `[tariff]` section, if configured
+    #[serde(default)]
+    pub cost: Option<CostSummary>,
+
+    /// Alerts active at this reading; the full list is `AppState::alert_list`
+    #[serde(default)]
+    pub active_alerts: Vec<ActiveAlert>,
+
     #[allow(dead_code)]
+    #[serde(skip, default = "Instant::now")]
     pub timestamp: Instant,
+
+    /// Wall-clock time of the reading (when it was received, for an attached client)
+    #[serde(skip, default = "chrono::Utc::now")]
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Why a configured metric has no value in a reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    Unavailable, // Home Assistant reports the entity as `unavailable`
+    Unknown,     // `unknown` or any other non-numeric state
+    FetchFailed, // The request for the entity failed
+}
+
+impl PowerData {
+    /// False when the metric's sensor (or an input of a derived value) had no usable value
+    pub fn is_available(&self, metric: &str) -> bool {
+        !self.unavailable.contains_key(metric)
+    }
+
+    /// Every name `numeric_fields` can return, including the optional sections; used to
+    /// validate metric names in the config
+    pub const METRICS: &'static [&'static str] = &[
+        "solar",
+        "grid_import",
+        "grid_export",
+        "load",
+        "load_current",
+        "battery_power",
+        "battery_voltage",
+        "battery_soc",
+        "battery_temp",
+        "battery_current",
+        "inverter_temp",
+        "inverter_voltage",
+        "inverter_frequency",
+        "grid_voltage",
+        "grid_ct_power",
+        "load_power_factor",
+        "grid_power_factor",
+        "day_battery_charge",
+        "day_battery_discharge",
+        "day_grid_import",
+        "day_grid_export",
+        "day_load_energy",
+        "day_pv_energy",
+        "total_pv_generation",
+        "remaining_solar",
+        "dc_transformer_temp",
+        "radiator_temp",
+        "essential_power",
+        "all_time_energy_usage_peak",
+        "all_time_load_peak",
+        "all_time_pv_power_peak",
+        "all_time_pv_yield_peak",
+        "daily_pv_power_peak",
+        "load_ratio",
+        "night_consume",
+        "pv_forecast_remain",
+        "pv_forecast_today",
+        "load_energy_yesterday",
+        "load_energy_total",
+        "day_consume",
+        "self_consumption",
+        "self_sufficiency",
+        "day_self_consumption",
+        "day_self_sufficiency",
+        "battery_power_smoothed",
+        "battery_stored_kwh",
+        "battery_time_to_full_seconds",
+        "battery_time_to_reserve_seconds",
+        "battery_cycles",
+        "battery_depth_of_discharge",
+        "battery_round_trip_efficiency",
+        "battery_usable_capacity_kwh",
+        "battery_state_of_health",
+        "overnight_available_kwh",
+        "overnight_needed_kwh",
+        "overnight_shortfall_kwh",
+        "current_price",
+        "today_on_peak_kwh",
+        "today_off_peak_kwh",
+        "today_cost",
+        "today_savings",
+        "month_cost",
+        "month_savings",
+        "year_cost",
+        "year_savings",
+        "bill_kwh",
+        "bill_total",
+        "roi_savings",
+        "roi_paid_back_percent",
+        "surplus_sustained",
+        "surplus_loads_switched",
+        "alerts_active",
+    ];
+
+    /// All numeric readings as (field name, value) pairs, in declaration order
+    pub fn numeric_fields(&self) -> Vec<(&'static str, f64)> {
+        let mut fields = vec![
+            ("solar", self.solar),
+            ("grid_import", self.grid_import),
+            ("grid_export", self.grid_export),
+            ("load", self.load),
+            ("load_current", self.load_current),
+            ("battery_power", self.battery_power),
+            ("battery_voltage", self.battery_voltage),
+            ("battery_soc", self.battery_soc),
+            ("battery_temp", self.battery_temp),
+            ("battery_current", self.battery_current),
+            ("inverter_temp", self.inverter_temp),
+            ("inverter_voltage", self.inverter_voltage),
+            ("inverter_frequency", self.inverter_frequency),
+            ("grid_voltage", self.grid_voltage),
+            ("grid_ct_power", self.grid_ct_power),
+            ("load_power_factor", self.load_power_factor),
+            ("grid_power_factor", self.grid_power_factor),
+            ("day_battery_charge", self.day_battery_charge),
+            ("day_battery_discharge", self.day_battery_discharge),
+            ("day_grid_import", self.day_grid_import),
+            ("day_grid_export", self.day_grid_export),
+            ("day_load_energy", self.day_load_energy),
+            ("day_pv_energy", self.day_pv_energy),
+            ("total_pv_generation", self.total_pv_generation),
+            ("remaining_solar", self.remaining_solar),
+            ("dc_transformer_temp", self.dc_transformer_temp),
+            ("radiator_temp", self.radiator_temp),
+            ("essential_power", self.essential_power),
+            (
+                "all_time_energy_usage_peak",
+                self.all_time_energy_usage_peak,
+            ),
+            ("all_time_load_peak", self.all_time_load_peak),
+            ("all_time_pv_power_peak", self.all_time_pv_power_peak),
+            ("all_time_pv_yield_peak", self.all_time_pv_yield_peak),
+            ("daily_pv_power_peak", self.daily_pv_power_peak),
+            ("load_ratio", self.load_ratio),
+            ("night_consume", self.night_consume),
+            ("pv_forecast_remain", self.pv_forecast_remain),
+            ("pv_forecast_today", self.pv_forecast_today),
+            ("load_energy_yesterday", self.load_energy_yesterday),
+            ("load_energy_total", self.load_energy_total),
+            ("day_consume", self.day_consume),
+            ("self_consumption", self.self_consumption),
+            ("self_sufficiency", self.self_sufficiency),
+            ("day_self_consumption", self.day_self_consumption),
+            ("day_self_sufficiency", self.day_self_sufficiency),
+            ("battery_power_smoothed", self.battery_power_smoothed),
+        ];
+        let optional = [
+            ("battery_stored_kwh", self.battery_stored_kwh),
+            ("battery_time_to_full_seconds", self.battery_time_to_full),
+            (
+                "battery_time_to_reserve_seconds",
+                self.battery_time_to_reserve,
+            ),
+        ];
+        fields.extend(
+            optional
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, value?))),
+        );
+        if let Some(health) = &self.battery_health {
+            fields.extend(health.numeric_fields());
+        }
+        if let Some(overnight) = &self.overnight {
+            fields.extend(overnight.numeric_fields());
+        }
+        if let Some(cost) = &self.cost {
+            fields.extend(cost.numeric_fields());
+        }
+        if let Some(surplus) = &self.surplus {
+            fields.extend(surplus.numeric_fields());
+        }
+        fields.push(("alerts_active", self.active_alerts.len() as f64));
+        // Unavailable readings are left out rather than reported as 0
+        fields.retain(|(name, _)| self.is_available(name));
+        fields
+    }
+
+    /// Fill in the derived ratios from the raw readings
+    pub fn compute_derived(&mut self) {
+        self.self_consumption = energy::self_consumption(self.solar, self.grid_export);
+        self.self_sufficiency = energy::self_sufficiency(self.load, self.grid_import);
+        self.day_self_consumption =
+            energy::self_consumption(self.day_pv_energy, self.day_grid_export);
+        self.day_self_sufficiency =
+            energy::self_sufficiency(self.day_load_energy, self.day_grid_import);
+
+        let inputs: [(&str, &[&str]); 5] = [
+            ("self_consumption", &["solar", "grid_export"]),
+            ("self_sufficiency", &["load", "grid_import"]),
+            (
+                "day_self_consumption",
+                &["day_pv_energy", "day_grid_export"],
+            ),
+            (
+                "day_self_sufficiency",
+                &["day_load_energy", "day_grid_import"],
+            ),
+            ("load_ratio", &["load"]),
+        ];
+        for (derived, metrics) in inputs {
+            if let Some(reason) = metrics.iter().find_map(|m| self.unavailable.get(*m)) {
+                self.unavailable.insert(derived.to_string(), *reason);
+            }
+        }
+    }
+}
+
+/// Battery operating state derived from battery power and float detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatteryState {
+    Charging,
+    Discharging,
+    Floating,
+    Idle,
+}
+
+impl BatteryState {
+    /// Classify battery power in Watts (positive = charging, negative = discharging)
+    pub fn from_power(power: f64, is_floating: bool) -> Self {
+        if power > 10.0 {
+            BatteryState::Charging
+        } else if power < -10.0 {
+            BatteryState::Discharging
+        } else if is_floating {
+            BatteryState::Floating
+        } else {
+            BatteryState::Idle
+        }
+    }
+}
+
+/// Severity of an alert (see `alerts`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// Snooze periods offered on the Alerts page, in order
+const SNOOZE_PERIODS: [Duration; 4] = [
+    Duration::from_secs(15 * 60),
+    Duration::from_secs(60 * 60),
+    Duration::from_secs(4 * 60 * 60),
+    Duration::from_secs(24 * 60 * 60),
+];
+
+/// How long an action result stays in the footer
+const STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The actions menu, opened with `x`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActionMenu {
+    pub selected: usize,
+    pub confirming: bool, // Waiting for the user to confirm the selected action
+}
+
+/// Transient message in the footer, e.g. the result of an action
+#[derive(Debug, Clone)]
+pub struct StatusMessage {
+    pub text: String,
+    pub ok: bool,
+    pub at: Instant,
+}
+
+/// Part a process plays against Home Assistant; only the owner has side effects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Owner,    // Polls and holds ~/.emon/poller.lock: the first TUI, daemon or collector
+    Observer, // Polls without side effects: snapshot, stream, or a poller started second
+    Attached, // Renders frames from a collector (`--attach`) and evaluates nothing
+}
+
+impl Role {
+    /// Take the poller lock for `Role::Owner`; another process holding it makes this an observer
+    fn claim(self) -> (Self, Option<File>) {
+        if self != Role::Owner {
+            return (self, None);
+        }
+        let file = config::get_config_dir().ok().and_then(|dir| {
+            fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(dir.join("poller.lock"))
+                .ok()
+        });
+        let Some(file) = file else {
+            return (Role::Owner, None); // No shared state directory to coordinate through
+        };
+        match file.try_lock() {
+            Ok(()) => (Role::Owner, Some(file)),
+            Err(TryLockError::WouldBlock) => (Role::Observer, None),
+            Err(TryLockError::Error(_)) => (Role::Owner, None), // Locking unsupported here
+        }
+    }
+}
+
+/// Top-level TUI pages, cycled with Tab or selected with number keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Page {
+    Dashboard,
+    PowerFlow,
+    Energy,
+    Statistics,
+    Battery,
+    Alerts,
+}
+
+impl Page {
+    pub const ALL: [Page; 6] = [
+        Page::Dashboard,
+        Page::PowerFlow,
+        Page::Energy,
+        Page::Statistics,
+        Page::Battery,
+        Page::Alerts,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Page::Dashboard => "Dashboard",
+            Page::PowerFlow => "Power Flow",
+            Page::Energy => "Energy Breakdown",
+            Page::Statistics => "Statistics",
+            Page::Battery => "Battery",
+            Page::Alerts => "Alerts",
+        }
+    }
+
+    fn index(self) -> usize {
+        Page::ALL.iter().position(|p| *p == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Self {
+        Page::ALL[(self.index() + 1) % Page::ALL.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        Page::ALL[(self.index() + Page::ALL.len() - 1) % Page::ALL.len()]
+    }
+
+    /// Page for a 1-based number key
+    pub fn from_number(n: u32) -> Option<Self> {
+        Page::ALL.get((n as usize).checked_sub(1)?).copied()
+    }
 }
 
 #[derive(Debug)]
@@ -87,12 +497,36 @@ pub struct AppState {
     pub ha: HomeAssistant,
     pub history: Vec<PowerData>,
     pub last_fetch: Option<Instant>,
+    pub last_update_duration: Option<Duration>,
     pub error: Option<String>,
     pub max_values: PowerData,
+    pub attached_to: Option<String>, // Collector address when rendering from `--attach`
+    pub role: Role,
+    _poller_lock: Option<File>, // Held for the lifetime of the owner
+    pub page: Page,
+    pub alert_selected: usize, // Row selected on the Alerts page
+    pub action_menu: Option<ActionMenu>,
+    pub inspector: Option<Inspector>,
+    pub status: Option<StatusMessage>,
+    pub alert_list: Vec<Alert>, // Active alerts, most severe first, then recently cleared ones
+    integrator: EnergyIntegrator,
+    battery: BatteryEstimator,
+    soc_estimator: SocEstimator,
+    health: Option<HealthTracker>,
+    alerts: AlertEngine,
+    publisher: Option<Publisher>,
+    surplus: Option<SurplusAdvisor>,
+    night: NightForecaster,
+    cost: Option<CostTracker>,
 }
 
 impl AppState {
-    pub fn new(config: Config, config_path: Option<PathBuf>) -> Self {
+    /// `role` is what the caller asks for; an owner becomes an observer if another process
+    /// already owns polling
+    pub fn new(config: Config, config_path: Option<PathBuf>, role: Role) -> Self {
+        let (role, poller_lock) = role.claim();
+        // Only the owner writes ~/.emon state; the others would overwrite it with stale copies
+        let owner = role == Role::Owner;
         let ha = HomeAssistant::new(
             config.home_assistant.url.clone(),
             config.home_assistant.token.clone(),
@@ -100,13 +534,60 @@ impl AppState {
 
         let history_size = config.home_assistant.get_history_seconds();
 
+        // The tariff was validated when the config was loaded
+        let cost = config
+            .tariff
+            .as_ref()
+            .and_then(|t| Tariff::from_config(t).ok())
+            .map(|t| CostTracker::new(t, config.roi.clone(), owner));
+        let alerts = AlertEngine::new(&config, owner);
+        let publisher = config
+            .publish
+            .clone()
+            .filter(|p| p.enabled && !config.read_only && owner)
+            .map(|publish| {
+                // A client of its own keeps publishing out of the fetch statistics
+                let ha = HomeAssistant::new(
+                    config.home_assistant.url.clone(),
+                    config.home_assistant.token.clone(),
+                );
+                Publisher::new(publish, ha)
+            });
+        let surplus = config
+            .surplus
+            .clone()
+            .map(|surplus| SurplusAdvisor::new(surplus, owner));
+        let health = config
+            .home_assistant
+            .battery_capacity_kwh
+            .map(|capacity_kwh| HealthTracker::new(capacity_kwh, owner));
+
         Self {
             config,
             config_path,
             ha,
             history: Vec::with_capacity(history_size),
             last_fetch: None,
+            last_update_duration: None,
             error: None,
+            attached_to: None,
+            role,
+            _poller_lock: poller_lock,
+            page: Page::Dashboard,
+            alert_selected: 0,
+            action_menu: None,
+            inspector: None,
+            status: None,
+            alert_list: Vec::new(),
+            integrator: EnergyIntegrator::new(owner),
+            battery: BatteryEstimator::default(),
+            soc_estimator: SocEstimator::new(owner),
+            health,
+            alerts,
+            publisher,
+            surplus,
+            night: NightForecaster::new(owner),
+            cost,
             max_values: PowerData {
                 solar: 0.0,
                 grid_import: 0.0,
@@ -152,13 +633,32 @@ impl AppState {
                 load_energy_yesterday: 0.0,
                 load_energy_total: 0.0,
                 day_consume: 0.0,
+                self_consumption: 0.0,
+                self_sufficiency: 0.0,
+                day_self_consumption: 0.0,
+                day_self_sufficiency: 0.0,
+                computed_daily: Vec::new(),
+                unavailable: BTreeMap::new(),
+                battery_soc_estimated: false,
+                battery_power_smoothed: 0.0,
+                battery_stored_kwh: None,
+                battery_time_to_full: None,
+                battery_time_to_reserve: None,
+                battery_health: None,
+                overnight: None,
+                surplus: None,
+                cost: None,
+                active_alerts: Vec::new(),
                 inverter_status: String::from("Unknown"),
                 timestamp: Instant::now(),
+                fetched_at: chrono::Utc::now(),
             },
         }
     }
 
     pub async fn update(&mut self) -> Result<()> {
+        let started = Instant::now();
+
         // Core power sensors
         let solar = self
             .ha
@@ -179,119 +679,119 @@ impl AppState {
 
         // Load current (optional)
         let load_current = match &self.config.home_assistant.entities.load_current {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
         // Battery sensors
         let bat_power = match &self.config.home_assistant.entities.battery_power {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let bat_voltage = match &self.config.home_assistant.entities.battery_voltage {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let bat_soc = match &self.config.home_assistant.entities.battery_soc {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let bat_temp = match &self.config.home_assistant.entities.battery_temp {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let bat_current = match &self.config.home_assistant.entities.battery_current {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
         // Inverter sensors
         let inv_temp = match &self.config.home_assistant.entities.inverter_temp {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let inv_voltage = match &self.config.home_assistant.entities.inverter_voltage {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let inv_freq = match &self.config.home_assistant.entities.inverter_frequency {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let inv_status = match &self.config.home_assistant.entities.inverter_status {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
         // Grid sensors
         let grid_voltage = match &self.config.home_assistant.entities.grid_voltage {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let grid_ct_power = match &self.config.home_assistant.entities.grid_ct_power {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
         // Power factor sensors
         let load_power_factor = match &self.config.home_assistant.entities.load_power_factor {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let grid_power_factor = match &self.config.home_assistant.entities.grid_power_factor {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
         // Daily energy sensors
         let day_bat_charge = match &self.config.home_assistant.entities.day_battery_charge {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let day_bat_discharge = match &self.config.home_assistant.entities.day_battery_discharge {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let day_grid_imp = match &self.config.home_assistant.entities.day_grid_import {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let day_grid_exp = match &self.config.home_assistant.entities.day_grid_export {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let day_load_ene = match &self.config.home_assistant.entities.day_load_energy {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let day_pv_ene = match &self.config.home_assistant.entities.day_pv_energy {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
         // Total energy sensors
         let total_pv_gen = match &self.config.home_assistant.entities.total_pv_generation {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let remaining_sol = match &self.config.home_assistant.entities.remaining_solar {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
         // Temperature sensors
         let dc_trans_temp = match &self.config.home_assistant.entities.dc_transformer_temp {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let rad_temp = match &self.config.home_assistant.entities.radiator_temp {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
         // Essential power
         let ess_power = match &self.config.home_assistant.entities.essential_power {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
@@ -302,7 +802,7 @@ impl AppState {
             .entities
             .all_time_energy_usage_peak
         {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_energy_usage_peak_date = match &self
@@ -311,15 +811,15 @@ impl AppState {
             .entities
             .all_time_energy_usage_peak_date
         {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_load_peak = match &self.config.home_assistant.entities.all_time_load_peak {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_pv_power_peak = match &self.config.home_assistant.entities.all_time_pv_power_peak {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_pv_power_peak_date = match &self
@@ -328,11 +828,11 @@ impl AppState {
             .entities
             .all_time_pv_power_peak_date
         {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_pv_yield_peak = match &self.config.home_assistant.entities.all_time_pv_yield_peak {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_pv_yield_peak_date = match &self
@@ -341,150 +841,129 @@ impl AppState {
             .entities
             .all_time_pv_yield_peak_date
         {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_daily_pv_power_peak =
             match &self.config.home_assistant.entities.daily_pv_power_peak {
-                Some(entity) => self.ha.get_state(entity).await.ok(),
+                Some(entity) => Some(self.ha.get_state(entity).await),
                 None => None,
             };
         let stat_daily_pv_power_peak_date =
             match &self.config.home_assistant.entities.daily_pv_power_peak_date {
-                Some(entity) => self.ha.get_state(entity).await.ok(),
+                Some(entity) => Some(self.ha.get_state(entity).await),
                 None => None,
             };
         let stat_night_consume = match &self.config.home_assistant.entities.night_consume {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_pv_forecast_remain = match &self.config.home_assistant.entities.pv_forecast_remain
         {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_pv_forecast_today = match &self.config.home_assistant.entities.pv_forecast_today {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_load_energy_yesterday =
             match &self.config.home_assistant.entities.load_energy_yesterday {
-                Some(entity) => self.ha.get_state(entity).await.ok(),
+                Some(entity) => Some(self.ha.get_state(entity).await),
                 None => None,
             };
         let stat_load_energy_total = match &self.config.home_assistant.entities.load_energy_total {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
         let stat_day_consume = match &self.config.home_assistant.entities.day_consume {
-            Some(entity) => self.ha.get_state(entity).await.ok(),
+            Some(entity) => Some(self.ha.get_state(entity).await),
             None => None,
         };
 
-        let solar_val = solar.map(|e| self.parse_entity_value(&e)).unwrap_or(0.0);
-        let grid_import_val = grid_import
-            .map(|e| self.parse_entity_value(&e))
-            .unwrap_or(0.0);
-        let grid_export_val = grid_export
-            .map(|e| self.parse_entity_value(&e))
-            .unwrap_or(0.0);
-        let load_val = load.map(|e| self.parse_entity_value(&e)).unwrap_or(0.0);
+        // Treat the connection as down when none of the core sensors could be fetched
+        if let (Err(e), Err(_), Err(_), Err(_)) = (&solar, &grid_import, &grid_export, &load) {
+            anyhow::bail!("{:#}", e);
+        }
+
+        let mut unavailable = BTreeMap::new();
+        let solar_val = reading(&mut unavailable, "solar", Some(solar));
+        let grid_import_val = reading(&mut unavailable, "grid_import", Some(grid_import));
+        let grid_export_val = reading(&mut unavailable, "grid_export", Some(grid_export));
+        let load_val = reading(&mut unavailable, "load", Some(load));
 
-        let data = PowerData {
+        let mut data = PowerData {
             solar: solar_val,
             grid_import: grid_import_val,
             grid_export: grid_export_val,
             load: load_val,
-            load_current: load_current
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            battery_power: bat_power
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            battery_voltage: bat_voltage
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            battery_soc: bat_soc.map(|e| self.parse_entity_value(&e)).unwrap_or(0.0),
-            battery_temp: bat_temp.map(|e| self.parse_entity_value(&e)).unwrap_or(0.0),
-            battery_current: bat_current
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            inverter_temp: inv_temp.map(|e| self.parse_entity_value(&e)).unwrap_or(0.0),
-            inverter_voltage: inv_voltage
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            inverter_frequency: inv_freq.map(|e| self.parse_entity_value(&e)).unwrap_or(0.0),
+            load_current: reading(&mut unavailable, "load_current", load_current),
+            battery_power: reading(&mut unavailable, "battery_power", bat_power),
+            battery_voltage: reading(&mut unavailable, "battery_voltage", bat_voltage),
+            battery_soc: reading(&mut unavailable, "battery_soc", bat_soc),
+            battery_temp: reading(&mut unavailable, "battery_temp", bat_temp),
+            battery_current: reading(&mut unavailable, "battery_current", bat_current),
+            inverter_temp: reading(&mut unavailable, "inverter_temp", inv_temp),
+            inverter_voltage: reading(&mut unavailable, "inverter_voltage", inv_voltage),
+            inverter_frequency: reading(&mut unavailable, "inverter_frequency", inv_freq),
             inverter_status: inv_status
+                .and_then(Result::ok)
                 .map(|e| e.state.clone())
                 .unwrap_or_else(|| "Unknown".to_string()),
-            grid_voltage: grid_voltage
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            grid_ct_power: grid_ct_power
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            load_power_factor: load_power_factor
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            grid_power_factor: grid_power_factor
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            day_battery_charge: day_bat_charge
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            day_battery_discharge: day_bat_discharge
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            day_grid_import: day_grid_imp
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            day_grid_export: day_grid_exp
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            day_load_energy: day_load_ene
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            day_pv_energy: day_pv_ene
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            total_pv_generation: total_pv_gen
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            remaining_solar: remaining_sol
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            dc_transformer_temp: dc_trans_temp
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            radiator_temp: rad_temp.map(|e| self.parse_entity_value(&e)).unwrap_or(0.0),
-            essential_power: ess_power
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            all_time_energy_usage_peak: stat_energy_usage_peak
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
+            grid_voltage: reading(&mut unavailable, "grid_voltage", grid_voltage),
+            grid_ct_power: reading(&mut unavailable, "grid_ct_power", grid_ct_power),
+            load_power_factor: reading(&mut unavailable, "load_power_factor", load_power_factor),
+            grid_power_factor: reading(&mut unavailable, "grid_power_factor", grid_power_factor),
+            day_battery_charge: reading(&mut unavailable, "day_battery_charge", day_bat_charge),
+            day_battery_discharge: reading(
+                &mut unavailable,
+                "day_battery_discharge",
+                day_bat_discharge,
+            ),
+            day_grid_import: reading(&mut unavailable, "day_grid_import", day_grid_imp),
+            day_grid_export: reading(&mut unavailable, "day_grid_export", day_grid_exp),
+            day_load_energy: reading(&mut unavailable, "day_load_energy", day_load_ene),
+            day_pv_energy: reading(&mut unavailable, "day_pv_energy", day_pv_ene),
+            total_pv_generation: reading(&mut unavailable, "total_pv_generation", total_pv_gen),
+            remaining_solar: reading(&mut unavailable, "remaining_solar", remaining_sol),
+            dc_transformer_temp: reading(&mut unavailable, "dc_transformer_temp", dc_trans_temp),
+            radiator_temp: reading(&mut unavailable, "radiator_temp", rad_temp),
+            essential_power: reading(&mut unavailable, "essential_power", ess_power),
+            all_time_energy_usage_peak: reading(
+                &mut unavailable,
+                "all_time_energy_usage_peak",
+                stat_energy_usage_peak,
+            ),
             all_time_energy_usage_peak_date: stat_energy_usage_peak_date
+                .and_then(Result::ok)
                 .map(|e| e.state.clone())
                 .unwrap_or_default(),
-            all_time_load_peak: stat_load_peak
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            all_time_pv_power_peak: stat_pv_power_peak
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
+            all_time_load_peak: reading(&mut unavailable, "all_time_load_peak", stat_load_peak),
+            all_time_pv_power_peak: reading(
+                &mut unavailable,
+                "all_time_pv_power_peak",
+                stat_pv_power_peak,
+            ),
             all_time_pv_power_peak_date: stat_pv_power_peak_date
+                .and_then(Result::ok)
                 .map(|e| e.state.clone())
                 .unwrap_or_default(),
-            all_time_pv_yield_peak: stat_pv_yield_peak
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
+            all_time_pv_yield_peak: reading(
+                &mut unavailable,
+                "all_time_pv_yield_peak",
+                stat_pv_yield_peak,
+            ),
             all_time_pv_yield_peak_date: stat_pv_yield_peak_date
+                .and_then(Result::ok)
                 .map(|e| e.state.clone())
                 .unwrap_or_default(),
-            daily_pv_power_peak: stat_daily_pv_power_peak
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
+            daily_pv_power_peak: reading(
+                &mut unavailable,
+                "daily_pv_power_peak",
+                stat_daily_pv_power_peak,
+            ),
             daily_pv_power_peak_date: stat_daily_pv_power_peak_date
+                .and_then(Result::ok)
                 .map(|e| e.state.clone())
                 .unwrap_or_default(),
             load_ratio: {
@@ -499,27 +978,93 @@ impl AppState {
                     0.0
                 }
             },
-            night_consume: stat_night_consume
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            pv_forecast_remain: stat_pv_forecast_remain
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            pv_forecast_today: stat_pv_forecast_today
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            load_energy_yesterday: stat_load_energy_yesterday
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            load_energy_total: stat_load_energy_total
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
-            day_consume: stat_day_consume
-                .map(|e| self.parse_entity_value(&e))
-                .unwrap_or(0.0),
+            night_consume: reading(&mut unavailable, "night_consume", stat_night_consume),
+            pv_forecast_remain: reading(
+                &mut unavailable,
+                "pv_forecast_remain",
+                stat_pv_forecast_remain,
+            ),
+            pv_forecast_today: reading(
+                &mut unavailable,
+                "pv_forecast_today",
+                stat_pv_forecast_today,
+            ),
+            load_energy_yesterday: reading(
+                &mut unavailable,
+                "load_energy_yesterday",
+                stat_load_energy_yesterday,
+            ),
+            load_energy_total: reading(
+                &mut unavailable,
+                "load_energy_total",
+                stat_load_energy_total,
+            ),
+            day_consume: reading(&mut unavailable, "day_consume", stat_day_consume),
+            self_consumption: 0.0,
+            self_sufficiency: 0.0,
+            day_self_consumption: 0.0,
+            day_self_sufficiency: 0.0,
+            computed_daily: Vec::new(),
+            unavailable,
+            battery_soc_estimated: false,
+            battery_power_smoothed: 0.0,
+            battery_stored_kwh: None,
+            battery_time_to_full: None,
+            battery_time_to_reserve: None,
+            battery_health: None,
+            overnight: None,
+            surplus: None,
+            cost: None,
+            active_alerts: Vec::new(),
             timestamp: Instant::now(),
+            fetched_at: chrono::Utc::now(),
         };
+        let now = data
+            .fetched_at
+            .with_timezone(&self.config.home_assistant.get_timezone());
+        self.integrator.apply(
+            &mut data,
+            &self.config.home_assistant.entities,
+            now.date_naive(),
+        );
+        data.compute_derived();
+        let floating = self.is_battery_floating(data.battery_voltage, data.battery_power);
+        self.soc_estimator
+            .apply(&mut data, &self.config.home_assistant, floating);
+        self.battery.apply(&mut data, &self.config.home_assistant);
+        if let Some(health) = self.health.as_mut() {
+            let has_soc = (self.config.home_assistant.entities.battery_soc.is_some()
+                && data.is_available("battery_soc"))
+                || data.battery_soc_estimated;
+            let soc = has_soc.then_some(data.battery_soc);
+            health.apply(&mut data, now.date_naive(), soc, floating);
+        }
+        self.night
+            .apply(&mut data, &self.config.home_assistant, now.timezone());
+        if let Some(cost) = self.cost.as_mut() {
+            cost.apply(&mut data, now);
+        }
+        if let Some(surplus) = self.surplus.as_mut() {
+            // Only one process may switch loads, or concurrent viewers fight over them
+            let auto = !self.config.read_only && self.role == Role::Owner;
+            surplus
+                .apply(&self.ha, &mut data, now, floating, auto)
+                .await;
+        }
+        self.alerts.apply(&mut data, now);
+        self.alert_list = self.alerts.alerts();
 
+        self.last_update_duration = Some(started.elapsed());
+        if let Some(publisher) = self.publisher.as_mut() {
+            publisher.publish(&data, &self.alert_list);
+        }
+        self.record(data);
+
+        Ok(())
+    }
+
+    /// Append a reading to history, whether fetched locally or received from a collector
+    pub fn record(&mut self, data: PowerData) {
         self.update_max_values(&data);
         self.history.push(data);
 
@@ -530,23 +1075,206 @@ impl AppState {
 
         self.last_fetch = Some(Instant::now());
         self.error = None;
+    }
 
-        Ok(())
+    /// Errors from publishing to Home Assistant since the last call
+    pub fn publish_failures(&mut self) -> Vec<String> {
+        self.publisher
+            .as_mut()
+            .map(|p| p.take_failures())
+            .unwrap_or_default()
     }
 
-    fn parse_entity_value(&self, entity: &EntityState) -> f64 {
-        entity.state.parse::<f64>().unwrap_or(0.0)
+    /// Alert selected on the Alerts page
+    pub fn selected_alert(&self) -> Option<&Alert> {
+        self.alert_list.get(self.alert_selected)
+    }
+
+    /// Move the Alerts page selection by `delta` rows
+    pub fn move_alert_selection(&mut self, delta: isize) {
+        let count = self.alert_list.len();
+        self.alert_selected = self
+            .alert_selected
+            .saturating_add_signed(delta)
+            .min(count.saturating_sub(1));
+    }
+
+    /// Acknowledge the selected alert, silencing its remaining actions
+    pub fn acknowledge_selected_alert(&mut self) {
+        let Some(alert) = self.managed_alert() else {
+            return;
+        };
+        self.alerts.acknowledge(&alert);
+        self.refresh_alerts();
+    }
+
+    /// Snooze the selected alert's rule, each press choosing the next longer period
+    pub fn snooze_selected_alert(&mut self) {
+        let Some(alert) = self.managed_alert() else {
+            return;
+        };
+        let now = chrono::Utc::now().with_timezone(&self.config.home_assistant.get_timezone());
+        let remaining = alert
+            .snoozed_until
+            .as_deref()
+            .and_then(|until| chrono::DateTime::parse_from_rfc3339(until).ok())
+            .and_then(|until| (until.with_timezone(&now.timezone()) - now).to_std().ok())
+            .unwrap_or_default();
+        let period = SNOOZE_PERIODS
+            .iter()
+            .copied()
+            .find(|p| *p > remaining + Duration::from_secs(60))
+            .unwrap_or(SNOOZE_PERIODS[0]);
+        self.alerts.snooze(&alert, Some(period), now);
+        self.refresh_alerts();
+    }
+
+    /// Lift the snooze of the selected alert's rule
+    pub fn unsnooze_selected_alert(&mut self) {
+        let Some(alert) = self.managed_alert() else {
+            return;
+        };
+        let now = chrono::Utc::now().with_timezone(&self.config.home_assistant.get_timezone());
+        self.alerts.snooze(&alert, None, now);
+        self.refresh_alerts();
+    }
+
+    /// The selected alert, if this process evaluates the rules
+    fn managed_alert(&mut self) -> Option<Alert> {
+        if let Some(addr) = &self.attached_to {
+            self.error = Some(format!("Alerts are managed by the collector at {}", addr));
+            return None;
+        }
+        self.selected_alert().cloned()
+    }
+
+    /// Show acknowledgements and snoozes without waiting for the next fetch
+    fn refresh_alerts(&mut self) {
+        self.alert_list = self.alerts.alerts();
+    }
+
+    /// Open the actions menu, or explain in the footer why it is unavailable
+    pub fn open_action_menu(&mut self) {
+        if self.config.read_only {
+            self.set_status("Actions are disabled (read_only = true)", false);
+        } else if self.config.actions.is_empty() {
+            self.set_status("No [[actions]] configured", false);
+        } else {
+            self.action_menu = Some(ActionMenu::default());
+        }
+    }
+
+    /// Move the actions menu selection by `delta` rows
+    pub fn move_action_selection(&mut self, delta: isize) {
+        let count = self.config.actions.len();
+        if let Some(menu) = self.action_menu.as_mut() {
+            menu.selected = menu
+                .selected
+                .saturating_add_signed(delta)
+                .min(count.saturating_sub(1));
+        }
+    }
+
+    /// Call the service of action `index` and report the outcome in the footer
+    pub async fn run_action(&mut self, index: usize) {
+        let Some(action) = self.config.actions.get(index).cloned() else {
+            return;
+        };
+        if self.config.read_only {
+            return;
+        }
+        let (domain, service) = action.domain_service();
+        let data = serde_json::to_value(&action.data).unwrap_or_default();
+        match self.ha.call_service(domain, service, &data).await {
+            Ok(changed) => {
+                let text = match changed.as_array().map(Vec::len) {
+                    Some(n) if n > 0 => format!("✓ {}: done, {} entities changed", action.name, n),
+                    _ => format!("✓ {}: done", action.name),
+                };
+                self.set_status(text, true);
+            }
+            Err(e) => self.set_status(format!("✗ {}: {:#}", action.name, e), false),
+        }
+    }
+
+    /// Fetch every Home Assistant entity for the inspector's second tab
+    pub async fn load_all_entities(&mut self) {
+        let result = self
+            .ha
+            .get_all_states()
+            .await
+            .map(|mut states| {
+                states.sort_by(|a, b| a.entity_id.cmp(&b.entity_id));
+                states
+            })
+            .map_err(|e| format!("{:#}", e));
+        if let Some(inspector) = self.inspector.as_mut() {
+            inspector.all = Some(result);
+            inspector.selected = 0;
+        }
+    }
+
+    pub fn set_status(&mut self, text: impl Into<String>, ok: bool) {
+        self.status = Some(StatusMessage {
+            text: text.into(),
+            ok,
+            at: Instant::now(),
+        });
+    }
+
+    /// The footer message, until it expires
+    pub fn current_status(&self) -> Option<&StatusMessage> {
+        self.status
+            .as_ref()
+            .filter(|s| s.at.elapsed() < STATUS_TIMEOUT)
+    }
+
+    /// Replace history wholesale (used when attaching to a collector)
+    pub fn replace_history(&mut self, history: Vec<PowerData>) {
+        self.history.clear();
+        for data in history {
+            self.record(data);
+        }
+    }
+
+    /// Battery is floating when voltage sits at the configured float voltage with near-zero power
+    pub fn is_battery_floating(&self, battery_voltage: f64, battery_power: f64) -> bool {
+        let float_voltage_threshold = self
+            .config
+            .home_assistant
+            .battery_float_voltage
+            .unwrap_or(54.0);
+        let voltage_tolerance = 0.5;
+
+        (battery_voltage >= float_voltage_threshold - voltage_tolerance)
+            && (battery_voltage <= float_voltage_threshold + voltage_tolerance)
+            && battery_power.abs() < 50.0
     }
 
     fn update_max_values(&mut self, data: &PowerData) {
-        self.max_values.solar = self.max_values.solar.max(data.solar);
-        self.max_values.grid_import = self.max_values.grid_import.max(data.grid_import);
-        self.max_values.grid_export = self.max_values.grid_export.max(data.grid_export);
-        self.max_values.load = self.max_values.load.max(data.load);
+        let max = &mut self.max_values;
+        for (metric, value, peak) in [
+            ("solar", data.solar, &mut max.solar),
+            ("grid_import", data.grid_import, &mut max.grid_import),
+            ("grid_export", data.grid_export, &mut max.grid_export),
+            ("load", data.load, &mut max.load),
+        ] {
+            if data.is_available(metric) {
+                *peak = peak.max(value);
+            }
+        }
+    }
+
+    /// History of one metric, None where the sample was unavailable
+    fn metric_history(&self, metric: &str, value: fn(&PowerData) -> f64) -> Vec<Option<f64>> {
+        self.history
+            .iter()
+            .map(|d| d.is_available(metric).then(|| value(d)))
+            .collect()
     }
 
-    pub fn get_solar_history(&self) -> Vec<f64> {
-        self.history.iter().map(|d| d.solar).collect()
+    pub fn get_solar_history(&self) -> Vec<Option<f64>> {
+        self.metric_history("solar", |d| d.solar)
     }
 
     pub fn get_history_seconds(&self) -> usize {
@@ -561,22 +1289,42 @@ impl AppState {
         self.config.home_assistant.get_history_time_unit()
     }
 
-    pub fn get_load_history(&self) -> Vec<f64> {
-        self.history.iter().map(|d| d.load).collect()
+    pub fn get_load_history(&self) -> Vec<Option<f64>> {
+        self.metric_history("load", |d| d.load)
     }
 
     #[allow(dead_code)]
-    pub fn get_grid_import_history(&self) -> Vec<f64> {
-        self.history.iter().map(|d| d.grid_import).collect()
+    pub fn get_grid_import_history(&self) -> Vec<Option<f64>> {
+        self.metric_history("grid_import", |d| d.grid_import)
     }
 
     #[allow(dead_code)]
-    pub fn get_grid_export_history(&self) -> Vec<f64> {
-        self.history.iter().map(|d| d.grid_export).collect()
+    pub fn get_grid_export_history(&self) -> Vec<Option<f64>> {
+        self.metric_history("grid_export", |d| d.grid_export)
     }
 
-    #[allow(dead_code)]
-    pub fn get_battery_power_history(&self) -> Vec<f64> {
-        self.history.iter().map(|d| d.battery_power).collect()
+    pub fn get_battery_power_history(&self) -> Vec<Option<f64>> {
+        self.metric_history("battery_power", |d| d.battery_power)
     }
 }
+
+/// Numeric value of a fetched sensor, 0 when it has none; the reason is recorded under `metric`
+///
+/// `fetched` is None when the sensor is not configured.
+fn reading(
+    unavailable: &mut BTreeMap<String, Availability>,
+    metric: &str,
+    fetched: Option<Result<EntityState>>,
+) -> f64 {
+    let reason = match fetched {
+        None => return 0.0,
+        Some(Ok(entity)) => match entity.state.trim().parse::<f64>() {
+            Ok(value) if value.is_finite() => return value,
+            _ if entity.state == "unavailable" => Availability::Unavailable,
+            _ => Availability::Unknown,
+        },
+        Some(Err(_)) => Availability::FetchFailed,
+    };
+    unavailable.insert(metric.to_string(), reason);
+    0.0
+}
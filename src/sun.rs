@@ -0,0 +1,43 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Sunrise and sunset (UTC) for `date` at the given position, using the NOAA sunrise equation
+///
+/// Returns None during polar day or night. Longitude is positive east.
+pub fn sunrise_sunset(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    let n = (date - j2000).num_days() as f64;
+
+    // Mean solar time, solar mean anomaly and equation of the center
+    let j_star = n - longitude / 360.0;
+    let m = (357.5291 + 0.98560028 * j_star).rem_euclid(360.0);
+    let m_rad = m.to_radians();
+    let c = 1.9148 * m_rad.sin() + 0.02 * (2.0 * m_rad).sin() + 0.0003 * (3.0 * m_rad).sin();
+
+    // Ecliptic longitude, solar transit and declination
+    let lambda = (m + c + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    let transit = 2451545.0 + j_star + 0.0053 * m_rad.sin() - 0.0069 * (2.0 * lambda).sin();
+    let sin_decl = lambda.sin() * 23.4397_f64.to_radians().sin();
+    let cos_decl = sin_decl.asin().cos();
+
+    // Hour angle of the sun at -0.833° (refraction and solar disc)
+    let lat = latitude.to_radians();
+    let cos_hour_angle =
+        ((-0.833_f64).to_radians().sin() - lat.sin() * sin_decl) / (lat.cos() * cos_decl);
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+
+    let to_utc = |julian: f64| {
+        let unix = (julian - 2440587.5) * 86400.0;
+        DateTime::from_timestamp(unix as i64, 0)
+    };
+    Some((
+        to_utc(transit - hour_angle / 360.0)?,
+        to_utc(transit + hour_angle / 360.0)?,
+    ))
+}
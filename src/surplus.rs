@@ -0,0 +1,248 @@
+use chrono::DateTime;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::config::{self, SurplusConfig};
+use crate::homeassistant::HomeAssistant;
+use crate::state::PowerData;
+
+/// One configured load, as suggested during surplus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedLoad {
+    pub name: String,
+    pub power: f64,
+    pub fits: bool,     // Draw fits in the current export, with the hysteresis margin
+    pub switched: bool, // Switched on by emon and not yet off again
+}
+
+/// Excess solar right now and what could use it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurplusAdvice {
+    pub export: f64,
+    pub since: Option<String>,     // RFC 3339, start of the current surplus
+    pub sustained: bool,           // Surplus has lasted at least `for`
+    pub loads: Vec<SuggestedLoad>, // Largest draw first
+    pub last_action: Option<String>, // Latest automatic switch, or its failure
+}
+
+impl SurplusAdvice {
+    pub fn numeric_fields(&self) -> Vec<(&'static str, f64)> {
+        let switched = self.loads.iter().filter(|l| l.switched).count();
+        vec![
+            ("surplus_sustained", if self.sustained { 1.0 } else { 0.0 }),
+            ("surplus_loads_switched", switched as f64),
+        ]
+    }
+}
+
+/// Detects sustained export with a full battery and optionally switches loads to use it
+#[derive(Debug)]
+pub struct SurplusAdvisor {
+    config: SurplusConfig,
+    surplus_since: Option<(Instant, String)>, // Start, also as RFC 3339 for display
+    deficit_since: Option<Instant>,
+    switched: Vec<usize>, // Indexes into `config.loads`, in switch-on order
+    last_switch: Option<Instant>,
+    last_action: Option<String>,
+    path: Option<PathBuf>, // ~/.emon/surplus_loads.json, names of the switched loads
+}
+
+impl SurplusAdvisor {
+    /// Loads switched on before a restart are picked up again so they still get switched off;
+    /// without `persist` the list is read but never written
+    pub fn new(config: SurplusConfig, persist: bool) -> Self {
+        let path = config::get_config_dir()
+            .ok()
+            .map(|dir| dir.join("surplus_loads.json"));
+        let names: Vec<String> = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let switched = names
+            .iter()
+            .filter_map(|name| {
+                config
+                    .loads
+                    .iter()
+                    .position(|load| load.auto && load.entity.is_some() && load.name == *name)
+            })
+            .collect();
+
+        Self {
+            config,
+            surplus_since: None,
+            deficit_since: None,
+            switched,
+            last_switch: None,
+            last_action: None,
+            path: path.filter(|_| persist),
+        }
+    }
+
+    /// Update the surplus state and attach the advice; `auto` is false in read-only mode
+    pub async fn apply(
+        &mut self,
+        ha: &HomeAssistant,
+        data: &mut PowerData,
+        now: DateTime<Tz>,
+        floating: bool,
+        auto: bool,
+    ) {
+        let hold = self.config.get_duration();
+        let hysteresis = self.config.hysteresis;
+
+        // A surplus starts at min_export (with a full battery) and ends below min_export - hysteresis,
+        // so switching a load on does not immediately end it. An unavailable export changes neither.
+        let export_known = data.is_available("grid_export");
+        let starts = export_known
+            && data.grid_export >= self.config.min_export
+            && (floating || !self.config.require_float);
+        let ends = export_known && data.grid_export < self.config.min_export - hysteresis;
+        if self.surplus_since.is_none() && starts {
+            self.surplus_since = Some((data.timestamp, now.to_rfc3339()));
+        } else if ends {
+            self.surplus_since = None;
+        }
+
+        // Loads switched on by emon should not run from the grid or drain the battery
+        // An unavailable reading reads 0, which would hide a shortfall; keep the deficit state instead
+        if data.is_available("grid_import") && data.is_available("battery_power") {
+            let shortfall = data.grid_import + (-data.battery_power).max(0.0);
+            if shortfall > hysteresis {
+                self.deficit_since.get_or_insert(data.timestamp);
+            } else {
+                self.deficit_since = None;
+            }
+        }
+
+        let sustained = self
+            .surplus_since
+            .as_ref()
+            .is_some_and(|(at, _)| data.timestamp.saturating_duration_since(*at) >= hold);
+        let settled = self
+            .last_switch
+            .is_none_or(|at| data.timestamp.saturating_duration_since(at) >= hold);
+
+        if auto && settled {
+            if sustained {
+                self.switch_on_next(ha, data.grid_export, now).await;
+            } else if self
+                .deficit_since
+                .is_some_and(|at| data.timestamp.saturating_duration_since(at) >= hold)
+            {
+                self.switch_off_last(ha, now).await;
+            }
+        }
+
+        let mut loads: Vec<SuggestedLoad> = self
+            .config
+            .loads
+            .iter()
+            .enumerate()
+            .map(|(i, load)| SuggestedLoad {
+                name: load.name.clone(),
+                power: load.power,
+                fits: load.power + hysteresis <= data.grid_export,
+                switched: self.switched.contains(&i),
+            })
+            .collect();
+        loads.sort_by(|a, b| b.power.total_cmp(&a.power));
+
+        data.surplus = Some(SurplusAdvice {
+            export: data.grid_export,
+            since: self.surplus_since.as_ref().map(|(_, at)| at.clone()),
+            sustained,
+            loads,
+            last_action: self.last_action.clone(),
+        });
+    }
+
+    /// Switch on the largest automatic load that fits in the export
+    async fn switch_on_next(&mut self, ha: &HomeAssistant, export: f64, now: DateTime<Tz>) {
+        let next = self
+            .config
+            .loads
+            .iter()
+            .enumerate()
+            .filter(|(i, load)| load.auto && !self.switched.contains(i))
+            .filter(|(_, load)| load.power + self.config.hysteresis <= export)
+            .max_by(|(_, a), (_, b)| a.power.total_cmp(&b.power))
+            .map(|(i, _)| i);
+        let Some(index) = next else {
+            return;
+        };
+        if self.switch(ha, index, true, now).await {
+            self.switched.push(index);
+            self.save();
+        }
+        // The export is measured again over a full period before the next load
+        self.surplus_since = Some((Instant::now(), now.to_rfc3339()));
+    }
+
+    /// Switch off the load switched on most recently
+    async fn switch_off_last(&mut self, ha: &HomeAssistant, now: DateTime<Tz>) {
+        let Some(&index) = self.switched.last() else {
+            return;
+        };
+        if self.switch(ha, index, false, now).await {
+            self.switched.pop();
+            self.save();
+        }
+        self.deficit_since = Some(Instant::now());
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let names: Vec<&str> = self
+            .switched
+            .iter()
+            .map(|&i| self.config.loads[i].name.as_str())
+            .collect();
+        let Ok(json) = serde_json::to_string(&names) else {
+            return;
+        };
+
+        // Write to a temporary file first so a crash never leaves a truncated list
+        let tmp = path.with_extension("json.tmp");
+        if fs::write(&tmp, json).is_ok() {
+            let _ = fs::rename(&tmp, path);
+        }
+    }
+
+    async fn switch(
+        &mut self,
+        ha: &HomeAssistant,
+        index: usize,
+        on: bool,
+        now: DateTime<Tz>,
+    ) -> bool {
+        let load = self.config.loads[index].clone();
+        let Some(entity) = &load.entity else {
+            return false;
+        };
+        self.last_switch = Some(Instant::now());
+        let service = if on { "turn_on" } else { "turn_off" };
+        let time = now.format("%H:%M");
+        match ha
+            .call_service("homeassistant", service, &json!({ "entity_id": entity }))
+            .await
+        {
+            Ok(_) => {
+                let verb = if on { "on" } else { "off" };
+                self.last_action = Some(format!("{} switched {} at {}", load.name, verb, time));
+                true
+            }
+            Err(e) => {
+                self.last_action = Some(format!("{} at {}: {:#}", load.name, time, e));
+                false
+            }
+        }
+    }
+}
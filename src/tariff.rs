@@ -0,0 +1,630 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveTime, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::config::{self, RateBlock, RoiConfig, TariffConfig, TouWindow};
+use crate::roi::{self, Payback};
+use crate::state::PowerData;
+
+/// How often the cost ledger is written to disk (it is also written when the day changes)
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Built-in tariffs selectable with `preset = "<name>"`
+///
+/// PEA and MEA publish the same residential base rates; both names are accepted so the
+/// config reads like the bill. Ft changes every few months and is not part of the presets.
+pub const PRESETS: [&str; 8] = [
+    "pea-1.1.1",
+    "pea-1.1.2",
+    "pea-1.2.1",
+    "pea-1.2.2",
+    "mea-1.1.1",
+    "mea-1.1.2",
+    "mea-1.2.1",
+    "mea-1.2.2",
+];
+
+fn preset(name: &str) -> Option<TariffConfig> {
+    let (utility, code) = name.split_once('-')?;
+    if utility != "pea" && utility != "mea" {
+        return None;
+    }
+
+    let block = |up_to: Option<f64>, rate: f64| RateBlock { up_to, rate };
+    // Mon-Fri 09:00-22:00; weekends and `holidays` are off-peak all day
+    let thai_tou = || {
+        Some(vec![TouWindow {
+            days: ["mon", "tue", "wed", "thu", "fri"]
+                .iter()
+                .map(|d| d.to_string())
+                .collect(),
+            start: "09:00".to_string(),
+            end: "22:00".to_string(),
+        }])
+    };
+
+    let base = TariffConfig {
+        currency: Some("฿".to_string()),
+        vat_percent: Some(7.0),
+        ..Default::default()
+    };
+
+    let config = match code {
+        // Residential, up to 150 kWh per month
+        "1.1.1" => TariffConfig {
+            blocks: Some(vec![
+                block(Some(15.0), 2.3488),
+                block(Some(25.0), 2.9882),
+                block(Some(35.0), 3.2405),
+                block(Some(100.0), 3.6237),
+                block(Some(150.0), 3.7171),
+                block(Some(400.0), 4.2218),
+                block(None, 4.4217),
+            ]),
+            service_charge: Some(8.19),
+            ..base
+        },
+        // Residential, over 150 kWh per month
+        "1.1.2" => TariffConfig {
+            blocks: Some(vec![
+                block(Some(150.0), 3.2484),
+                block(Some(400.0), 4.2218),
+                block(None, 4.4217),
+            ]),
+            service_charge: Some(38.22),
+            ..base
+        },
+        // Residential TOU, 22-33 kV
+        "1.2.1" => TariffConfig {
+            rate: Some(2.6037),
+            on_peak_rate: Some(5.1135),
+            on_peak: thai_tou(),
+            service_charge: Some(312.24),
+            ..base
+        },
+        // Residential TOU, below 22 kV
+        "1.2.2" => TariffConfig {
+            rate: Some(2.6369),
+            on_peak_rate: Some(5.7982),
+            on_peak: thai_tou(),
+            service_charge: Some(38.22),
+            ..base
+        },
+        _ => return None,
+    };
+
+    Some(config)
+}
+
+/// Time-of-use period
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Period {
+    OnPeak,
+    OffPeak,
+}
+
+/// Parsed `[tariff]` section with the preset applied
+#[derive(Debug, Clone)]
+pub struct Tariff {
+    currency: String,
+    rate: f64,
+    on_peak_rate: f64,
+    blocks: Vec<RateBlock>,
+    windows: Vec<Window>,
+    holidays: Vec<NaiveDate>,
+    ft: f64,
+    ft_schedule: Vec<(NaiveDate, f64)>,
+    service_charge: f64,
+    vat_percent: f64,
+    export_rate: f64,
+    billing_day: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Window {
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl Window {
+    fn contains(&self, weekday: Weekday, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.applies_on(weekday) && time >= self.start && time < self.end
+        } else {
+            // Wraps past midnight: the part after midnight belongs to the previous day's window
+            (self.applies_on(weekday) && time >= self.start)
+                || (self.applies_on(weekday.pred()) && time < self.end)
+        }
+    }
+
+    fn applies_on(&self, weekday: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&weekday)
+    }
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date '{}', expected YYYY-MM-DD", s))
+}
+
+impl Tariff {
+    pub fn from_config(config: &TariffConfig) -> Result<Self> {
+        let preset = match &config.preset {
+            Some(name) => preset(name).with_context(|| {
+                format!(
+                    "Unknown tariff preset '{}' (available: {})",
+                    name,
+                    PRESETS.join(", ")
+                )
+            })?,
+            None => TariffConfig::default(),
+        };
+
+        let blocks = config.blocks.clone().or(preset.blocks).unwrap_or_default();
+        let rate = match (config.rate.or(preset.rate), blocks.is_empty()) {
+            (Some(rate), _) => rate,
+            (None, false) => 0.0, // Blocks set the price
+            (None, true) => anyhow::bail!("Set `rate`, `blocks` or a `preset`"),
+        };
+
+        let parse_time = |s: &str| {
+            NaiveTime::parse_from_str(s, "%H:%M")
+                .with_context(|| format!("Invalid time '{}', expected HH:MM", s))
+        };
+        let windows = config
+            .on_peak
+            .as_ref()
+            .or(preset.on_peak.as_ref())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|w| {
+                let days = w
+                    .days
+                    .iter()
+                    .map(|d| {
+                        d.parse::<Weekday>()
+                            .map_err(|_| anyhow::anyhow!("Invalid weekday '{}'", d))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Window {
+                    days,
+                    start: parse_time(&w.start)?,
+                    end: parse_time(&w.end)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if !blocks.is_empty() && !windows.is_empty() {
+            anyhow::bail!(
+                "`blocks` cannot be combined with `on_peak` windows: progressive blocks price the \
+                 whole cycle, so the on-peak rate would never apply"
+            );
+        }
+
+        let holidays = config
+            .holidays
+            .iter()
+            .map(|d| parse_date(d))
+            .collect::<Result<Vec<_>>>()?;
+        let mut ft_schedule = config
+            .ft_schedule
+            .iter()
+            .map(|p| Ok((parse_date(&p.from)?, p.ft)))
+            .collect::<Result<Vec<_>>>()?;
+        ft_schedule.sort_by_key(|(from, _)| *from);
+
+        let billing_day = config.billing_day.unwrap_or(1);
+        if !(1..=28).contains(&billing_day) {
+            anyhow::bail!("billing_day must be between 1 and 28");
+        }
+
+        Ok(Self {
+            currency: config
+                .currency
+                .clone()
+                .or(preset.currency)
+                .unwrap_or_else(|| "฿".to_string()),
+            rate,
+            on_peak_rate: config.on_peak_rate.or(preset.on_peak_rate).unwrap_or(rate),
+            blocks,
+            windows,
+            holidays,
+            ft: config.ft.or(preset.ft).unwrap_or(0.0),
+            ft_schedule,
+            service_charge: config
+                .service_charge
+                .or(preset.service_charge)
+                .unwrap_or(0.0),
+            vat_percent: config.vat_percent.or(preset.vat_percent).unwrap_or(0.0),
+            export_rate: config.export_rate.or(preset.export_rate).unwrap_or(0.0),
+            billing_day,
+        })
+    }
+
+    /// TOU period at `now`, or None for tariffs without on-peak windows
+    pub fn period_at(&self, now: DateTime<Tz>) -> Option<Period> {
+        if self.windows.is_empty() {
+            return None;
+        }
+        let (weekday, time) = (now.weekday(), now.time());
+        if !self.holidays.contains(&now.date_naive())
+            && self.windows.iter().any(|w| w.contains(weekday, time))
+        {
+            Some(Period::OnPeak)
+        } else {
+            Some(Period::OffPeak)
+        }
+    }
+
+    /// First day of the billing cycle containing `date`
+    pub fn cycle_start(&self, date: NaiveDate) -> NaiveDate {
+        let start = date.with_day(self.billing_day).unwrap_or(date);
+        if date.day() >= self.billing_day {
+            start
+        } else {
+            start - Months::new(1)
+        }
+    }
+
+    /// FT in effect for the billing cycle starting on `cycle_start`
+    fn ft_for(&self, cycle_start: NaiveDate) -> f64 {
+        self.ft_schedule
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= cycle_start)
+            .map(|(_, ft)| *ft)
+            .unwrap_or(self.ft)
+    }
+
+    /// Energy rate of the next kWh, given what was already imported this cycle; blocks and TOU
+    /// windows are exclusive (see `from_config`)
+    fn energy_rate(&self, period: Option<Period>, cycle_kwh: f64) -> f64 {
+        if let Some(block) = self
+            .blocks
+            .iter()
+            .find(|b| b.up_to.is_none_or(|up_to| cycle_kwh < up_to))
+        {
+            return block.rate;
+        }
+        match period {
+            Some(Period::OnPeak) => self.on_peak_rate,
+            _ => self.rate,
+        }
+    }
+
+    /// Price of the next imported kWh, including FT and VAT
+    pub fn import_price(
+        &self,
+        period: Option<Period>,
+        cycle_kwh: f64,
+        cycle_start: NaiveDate,
+    ) -> f64 {
+        (self.energy_rate(period, cycle_kwh) + self.ft_for(cycle_start))
+            * (1.0 + self.vat_percent / 100.0)
+    }
+
+    /// Cost of `kwh` imported on top of `cycle_kwh` already imported this cycle, including FT
+    /// and VAT; with progressive blocks each kWh is priced in the block it falls in
+    fn import_cost(
+        &self,
+        period: Option<Period>,
+        cycle_kwh: f64,
+        kwh: f64,
+        cycle_start: NaiveDate,
+    ) -> f64 {
+        let energy = if self.blocks.is_empty() {
+            kwh * self.energy_rate(period, cycle_kwh)
+        } else {
+            self.energy_charge(0.0, cycle_kwh + kwh) - self.energy_charge(0.0, cycle_kwh)
+        };
+        (energy + kwh * self.ft_for(cycle_start)) * (1.0 + self.vat_percent / 100.0)
+    }
+
+    /// Energy charge for a whole cycle, walking the progressive blocks
+    fn energy_charge(&self, on_peak_kwh: f64, off_peak_kwh: f64) -> f64 {
+        if self.blocks.is_empty() {
+            return on_peak_kwh * self.on_peak_rate + off_peak_kwh * self.rate;
+        }
+
+        let total = on_peak_kwh + off_peak_kwh;
+        let mut charge = 0.0;
+        let mut lower = 0.0;
+        for block in &self.blocks {
+            let upper = block.up_to.unwrap_or(f64::INFINITY).min(total);
+            if upper > lower {
+                charge += (upper - lower) * block.rate;
+                lower = upper;
+            }
+        }
+        charge
+    }
+
+    /// Bill for the cycle starting on `cycle_start` from its imported energy
+    pub fn bill(
+        &self,
+        cycle_start: NaiveDate,
+        on_peak_kwh: f64,
+        off_peak_kwh: f64,
+    ) -> BillEstimate {
+        let kwh = on_peak_kwh + off_peak_kwh;
+        let energy_charge = self.energy_charge(on_peak_kwh, off_peak_kwh);
+        let ft_charge = kwh * self.ft_for(cycle_start);
+        let subtotal = energy_charge + ft_charge + self.service_charge;
+        let vat = subtotal * self.vat_percent / 100.0;
+
+        BillEstimate {
+            cycle_start: cycle_start.format("%Y-%m-%d").to_string(),
+            kwh,
+            energy_charge,
+            ft_charge,
+            service_charge: self.service_charge,
+            vat,
+            total: subtotal + vat,
+        }
+    }
+}
+
+/// Energy and money accumulated for one local day
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DayCost {
+    pub on_peak_kwh: f64,
+    pub off_peak_kwh: f64,
+    pub import_cost: f64,
+    pub export_kwh: f64,
+    pub export_credit: f64,
+    pub pv_savings: f64, // Import cost avoided by using PV on site
+    pub pv_kwh: f64,
+}
+
+impl DayCost {
+    fn add(&mut self, other: &DayCost) {
+        self.on_peak_kwh += other.on_peak_kwh;
+        self.off_peak_kwh += other.off_peak_kwh;
+        self.import_cost += other.import_cost;
+        self.export_kwh += other.export_kwh;
+        self.export_credit += other.export_credit;
+        self.pv_savings += other.pv_savings;
+        self.pv_kwh += other.pv_kwh;
+    }
+
+    /// Grid bill after export credit
+    pub fn net_cost(&self) -> f64 {
+        self.import_cost - self.export_credit
+    }
+
+    /// Money saved by PV: avoided imports plus export credit
+    pub fn savings(&self) -> f64 {
+        self.pv_savings + self.export_credit
+    }
+}
+
+/// Utility bill for the current billing cycle so far
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BillEstimate {
+    pub cycle_start: String,
+    pub kwh: f64,
+    pub energy_charge: f64,
+    pub ft_charge: f64,
+    pub service_charge: f64,
+    pub vat: f64,
+    pub total: f64,
+}
+
+/// Costs attached to each reading when a `[tariff]` is configured
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostSummary {
+    pub currency: String,
+    pub period: Option<Period>, // None without TOU windows
+    pub current_price: f64,     // Price of the next imported kWh
+    pub today: DayCost,
+    pub month: DayCost,
+    pub year: DayCost,
+    #[serde(default)]
+    pub bill: BillEstimate,
+    #[serde(default)]
+    pub roi: Option<Payback>, // With a `[roi]` section
+}
+
+impl CostSummary {
+    pub fn numeric_fields(&self) -> Vec<(&'static str, f64)> {
+        let mut fields = vec![
+            ("current_price", self.current_price),
+            ("today_on_peak_kwh", self.today.on_peak_kwh),
+            ("today_off_peak_kwh", self.today.off_peak_kwh),
+            ("today_cost", self.today.net_cost()),
+            ("today_savings", self.today.savings()),
+            ("month_cost", self.month.net_cost()),
+            ("month_savings", self.month.savings()),
+            ("year_cost", self.year.net_cost()),
+            ("year_savings", self.year.savings()),
+            ("bill_kwh", self.bill.kwh),
+            ("bill_total", self.bill.total),
+        ];
+        if let Some(roi) = &self.roi {
+            fields.extend([
+                ("roi_savings", roi.savings),
+                ("roi_paid_back_percent", roi.paid_back_percent),
+            ]);
+        }
+        fields
+    }
+}
+
+/// Turns daily energy counters into costs and keeps a per-day ledger in ~/.emon
+#[derive(Debug)]
+pub struct CostTracker {
+    tariff: Tariff,
+    roi: Option<RoiConfig>,
+    ledger: BTreeMap<String, DayCost>, // Keyed by local date, YYYY-MM-DD
+    path: Option<PathBuf>,
+    last: Option<(String, [f64; 3])>, // Previous (import, export, pv) counters
+    last_save: Instant,
+    payback: Option<Payback>, // Kept while the lifetime PV counter is unavailable
+}
+
+impl CostTracker {
+    /// `persist` is false outside the owning poller, which would otherwise overwrite its ledger
+    pub fn new(tariff: Tariff, roi: Option<RoiConfig>, persist: bool) -> Self {
+        let path = config::get_config_dir()
+            .ok()
+            .map(|dir| dir.join("cost_ledger.json"));
+        let ledger = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            tariff,
+            roi,
+            ledger,
+            path: path.filter(|_| persist),
+            last: None,
+            last_save: Instant::now(),
+            payback: None,
+        }
+    }
+
+    /// Sum of ledger days on or after `from` (YYYY-MM-DD)
+    fn total_since(&self, from: &str) -> DayCost {
+        let mut total = DayCost::default();
+        for day in self.ledger.range(from.to_string()..).map(|(_, day)| day) {
+            total.add(day);
+        }
+        total
+    }
+
+    /// Charge the energy since the previous reading at the current rate and attach the totals
+    pub fn apply(&mut self, data: &mut PowerData, now: DateTime<Tz>) {
+        let today = now.format("%Y-%m-%d").to_string();
+        let cycle_start = self.tariff.cycle_start(now.date_naive());
+        let cycle_key = cycle_start.format("%Y-%m-%d").to_string();
+        let period = self.tariff.period_at(now);
+
+        let cycle = self.total_since(&cycle_key);
+        let cycle_kwh = cycle.on_peak_kwh + cycle.off_peak_kwh;
+        let price = self.tariff.import_price(period, cycle_kwh, cycle_start);
+        let counters = [
+            data.day_grid_import,
+            data.day_grid_export,
+            data.day_pv_energy,
+        ];
+
+        // An unavailable counter reads 0, which would look like a reset; keep the baseline instead
+        let available = ["day_grid_import", "day_grid_export", "day_pv_energy"]
+            .iter()
+            .all(|metric| data.is_available(metric));
+
+        if available {
+            let same_day = self
+                .last
+                .as_ref()
+                .filter(|(last_day, _)| *last_day == today);
+            let rising = |last: &[f64; 3]| counters.iter().zip(last).all(|(c, l)| c >= l);
+            let delta = match (same_day, &self.last) {
+                // Energy since the previous reading
+                (Some((_, last)), _) if rising(last) => {
+                    Some([0, 1, 2].map(|i| counters[i] - last[i]))
+                }
+                // Yesterday's counters have not reset yet; the drop once they do reconciles
+                (None, Some((_, last))) if rising(last) => None,
+                // After startup, a reset or a drop the counters hold the whole day so far;
+                // charge whatever the ledger is missing (e.g. while emon was not running)
+                _ => {
+                    let day = self.ledger.get(&today).copied().unwrap_or_default();
+                    let charged = [
+                        day.on_peak_kwh + day.off_peak_kwh,
+                        day.export_kwh,
+                        day.pv_kwh,
+                    ];
+                    Some([0, 1, 2].map(|i| (counters[i] - charged[i]).max(0.0)))
+                }
+            };
+            if self
+                .last
+                .as_ref()
+                .is_some_and(|(last_day, _)| *last_day != today)
+            {
+                self.save();
+            }
+            if let Some([import, export, pv]) = delta {
+                let day = self.ledger.entry(today.clone()).or_default();
+                match period {
+                    Some(Period::OnPeak) => day.on_peak_kwh += import,
+                    _ => day.off_peak_kwh += import,
+                }
+                day.import_cost += self
+                    .tariff
+                    .import_cost(period, cycle_kwh, import, cycle_start);
+                day.export_kwh += export;
+                day.export_credit += export * self.tariff.export_rate;
+                day.pv_savings += (pv - export).max(0.0) * price;
+                day.pv_kwh += pv;
+            }
+            self.last = Some((today.clone(), counters));
+        }
+
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+
+        // The next kWh is priced from the cycle total including what was just charged
+        let cycle = self.total_since(&cycle_key);
+        let price =
+            self.tariff
+                .import_price(period, cycle.on_peak_kwh + cycle.off_peak_kwh, cycle_start);
+        if data.is_available("total_pv_generation") {
+            self.payback = self.roi.as_ref().map(|roi| {
+                roi::payback(
+                    roi,
+                    &self.ledger,
+                    data.total_pv_generation,
+                    now.date_naive(),
+                    price,
+                )
+            });
+        }
+        data.cost = Some(CostSummary {
+            currency: self.tariff.currency.clone(),
+            period,
+            current_price: price,
+            today: self.ledger.get(&today).copied().unwrap_or_default(),
+            month: self.total_since(&format!("{}-01", &today[..7])),
+            year: self.total_since(&format!("{}-01-01", &today[..4])),
+            bill: self
+                .tariff
+                .bill(cycle_start, cycle.on_peak_kwh, cycle.off_peak_kwh),
+            roi: self.payback.clone(),
+        });
+    }
+
+    fn save(&mut self) {
+        self.last_save = Instant::now();
+        let Some(path) = &self.path else {
+            return;
+        };
+        let Ok(json) = serde_json::to_string_pretty(&self.ledger) else {
+            return;
+        };
+
+        // Write to a temporary file first so a crash never leaves a truncated ledger
+        let tmp = path.with_extension("json.tmp");
+        if fs::write(&tmp, json).is_ok() {
+            let _ = fs::rename(&tmp, path);
+        }
+    }
+}
+
+impl Drop for CostTracker {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
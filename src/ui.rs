@@ -4,12 +4,17 @@ use ratatui::{
     text::{Line, Span},
     widgets::{
         Axis, Block, BorderType, Borders, Chart, Clear, Dataset, Gauge, Padding, Paragraph, Row,
-        Table,
+        Sparkline, Table, TableState,
     },
     Frame,
 };
 
-use crate::state::AppState;
+use crate::alerts::{self, Alert};
+use crate::energy::{self, EnergyBreakdown};
+use crate::forecast::NightSource;
+use crate::inspector::{InspectorRow, InspectorTab};
+use crate::state::{AppState, BatteryState, Page, Role, Severity};
+use crate::tariff::Period;
 
 pub fn render(f: &mut Frame, app: &AppState) {
     let size = f.area();
@@ -23,23 +28,72 @@ pub fn render(f: &mut Frame, app: &AppState) {
         return;
     }
 
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(3),      // Header
-            Constraint::Percentage(9),  // Gauges
-            Constraint::Percentage(44), // Data tables + Daily energy (combined)
-            Constraint::Percentage(43), // Charts
-            Constraint::Length(1),      // Footer
-        ])
-        .split(size);
+    // Alerts panel below the header, only while an alert is active
+    let alerts_height = alerts_panel_height(app);
+
+    match app.page {
+        Page::Dashboard => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .margin(1)
+                .constraints([
+                    Constraint::Length(3),             // Header
+                    Constraint::Length(alerts_height), // Active alerts
+                    Constraint::Percentage(9),         // Gauges
+                    Constraint::Percentage(44),        // Data tables + Daily energy (combined)
+                    Constraint::Percentage(43),        // Charts
+                    Constraint::Length(1),             // Footer
+                ])
+                .split(size);
+
+            render_header(f, app, chunks[0]);
+            render_alerts_panel(f, app, chunks[1]);
+            render_main_gauges(f, app, chunks[2]);
+            render_combined_tables(f, app, chunks[3]); // New combined layout
+            render_charts(f, app, chunks[4]);
+            render_footer(f, app, chunks[5]);
+        }
+        page => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .margin(1)
+                .constraints([
+                    Constraint::Length(3),             // Header
+                    Constraint::Length(alerts_height), // Active alerts
+                    Constraint::Min(10),               // Page body
+                    Constraint::Length(1),             // Footer
+                ])
+                .split(size);
+
+            render_header(f, app, chunks[0]);
+            render_alerts_panel(f, app, chunks[1]);
+            match page {
+                Page::PowerFlow if app.config.surplus.is_some() => {
+                    let loads = app.config.surplus.as_ref().map_or(0, |s| s.loads.len());
+                    let sections = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Min(12), Constraint::Length(loads as u16 + 6)])
+                        .split(chunks[2]);
+                    render_power_flow(f, app, sections[0]);
+                    render_surplus(f, app, sections[1]);
+                }
+                Page::PowerFlow => render_power_flow(f, app, chunks[2]),
+                Page::Energy => render_energy_breakdown(f, app, chunks[2]),
+                Page::Statistics => render_statistics(f, app, chunks[2]),
+                Page::Battery => render_battery_health(f, app, chunks[2]),
+                Page::Alerts => render_alerts_page(f, app, chunks[2]),
+                Page::Dashboard => unreachable!(),
+            }
+            render_footer(f, app, chunks[3]);
+        }
+    }
 
-    render_header(f, app, chunks[0]);
-    render_main_gauges(f, app, chunks[1]);
-    render_combined_tables(f, app, chunks[2]); // New combined layout
-    render_charts(f, app, chunks[3]);
-    render_footer(f, chunks[4]);
+    if app.action_menu.is_some() {
+        render_action_menu(f, app, size);
+    }
+    if app.inspector.is_some() {
+        render_inspector(f, app, size);
+    }
 
     // Render error popup if there's an error
     if app.error.is_some() {
@@ -49,7 +103,6 @@ pub fn render(f: &mut Frame, app: &AppState) {
 
 fn render_header(f: &mut Frame, app: &AppState, area: Rect) {
     use chrono::Utc;
-    use chrono_tz::Tz;
 
     // Calculate elapsed time with millisecond precision
     let elapsed_ms = app
@@ -82,13 +135,7 @@ fn render_header(f: &mut Frame, app: &AppState, area: Rect) {
     };
 
     // Get current time in configured timezone with UTC offset format
-    let tz_str = app
-        .config
-        .home_assistant
-        .timezone
-        .as_deref()
-        .unwrap_or("Asia/Bangkok");
-    let tz: Tz = tz_str.parse().unwrap_or(chrono_tz::Asia::Bangkok);
+    let tz = app.config.home_assistant.get_timezone();
     let now_utc = Utc::now();
     let now_local = now_utc.with_timezone(&tz);
 
@@ -123,17 +170,30 @@ fn render_header(f: &mut Frame, app: &AppState, area: Rect) {
         ));
     }
 
+    // Attached clients show the collector they render from instead of Home Assistant
+    let (source_label, source) = match &app.attached_to {
+        Some(addr) => ("Attached to: ", addr.as_str()),
+        None => ("Connected to: ", app.config.home_assistant.url.as_str()),
+    };
+
     second_line_spans.push(Span::raw("    "));
     second_line_spans.push(Span::styled(
-        "Connected to: ",
+        source_label,
         Style::default().fg(Color::Rgb(150, 150, 150)),
     ));
     second_line_spans.push(Span::styled(
-        app.config.home_assistant.url.as_str(),
+        source,
         Style::default()
             .fg(Color::White)
             .add_modifier(Modifier::BOLD),
     ));
+    // Another emon process owns hooks, publishing, load switching and ~/.emon state
+    if app.role == Role::Observer {
+        second_line_spans.push(Span::styled(
+            " (viewer)",
+            Style::default().fg(Color::Rgb(150, 150, 150)),
+        ));
+    }
     second_line_spans.push(Span::raw("  |  "));
     second_line_spans.push(Span::styled(
         &refresh_str,
@@ -154,32 +214,86 @@ fn render_header(f: &mut Frame, app: &AppState, area: Rect) {
     }
 
     let header = Paragraph::new(vec![
-        Line::from(vec![
-            Span::styled(
-                "Real-time Energy Monitor",
-                Style::default()
-                    .fg(Color::LightCyan)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::raw("  "),
-            Span::styled(spinner, Style::default().fg(status_color)),
-            Span::raw(" "),
-            Span::styled(
-                status_text,
-                Style::default()
-                    .fg(status_color)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::raw("  |  "),
-            Span::styled(&time_str, Style::default().fg(Color::Rgb(200, 200, 100))),
-        ]),
+        Line::from(
+            vec![
+                Span::styled(
+                    "Real-time Energy Monitor",
+                    Style::default()
+                        .fg(Color::LightCyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  "),
+                Span::styled(spinner, Style::default().fg(status_color)),
+                Span::raw(" "),
+                Span::styled(
+                    status_text,
+                    Style::default()
+                        .fg(status_color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  |  "),
+                Span::styled(&time_str, Style::default().fg(Color::Rgb(200, 200, 100))),
+            ]
+            .into_iter()
+            .chain(rate_badge(app))
+            .collect::<Vec<_>>(),
+        ),
         Line::from(second_line_spans),
+        page_tabs_line(app.page),
     ])
     .alignment(Alignment::Center);
 
     f.render_widget(header, area);
 }
 
+/// Current tariff rate, e.g. "ON-PEAK 5.80 ฿/kWh", when costs are tracked
+fn rate_badge(app: &AppState) -> Vec<Span<'static>> {
+    let Some(cost) = app.history.last().and_then(|d| d.cost.as_ref()) else {
+        return Vec::new();
+    };
+    let (label, color) = match cost.period {
+        Some(Period::OnPeak) => ("ON-PEAK", Color::Rgb(255, 100, 100)),
+        Some(Period::OffPeak) => ("OFF-PEAK", Color::Rgb(100, 255, 100)),
+        None => ("RATE", Color::Rgb(100, 200, 255)),
+    };
+    vec![
+        Span::raw("  |  "),
+        Span::styled(
+            format!(
+                " {} {:.2} {}/kWh ",
+                label, cost.current_price, cost.currency
+            ),
+            Style::default()
+                .fg(Color::Black)
+                .bg(color)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ]
+}
+
+/// Page selector shown on the last header line: "1 Dashboard  2 Power Flow ..."
+fn page_tabs_line(current: Page) -> Line<'static> {
+    let mut spans = Vec::new();
+    for (idx, page) in Page::ALL.iter().enumerate() {
+        if idx > 0 {
+            spans.push(Span::raw("  "));
+        }
+        let style = if *page == current {
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Rgb(100, 200, 255))
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Rgb(150, 150, 150))
+        };
+        spans.push(Span::styled(
+            format!(" {} {} ", idx + 1, page.title()),
+            style,
+        ));
+    }
+    Line::from(spans)
+}
+
 fn render_main_gauges(f: &mut Frame, app: &AppState, area: Rect) {
     let latest = app.history.last();
 
@@ -205,18 +319,48 @@ fn render_main_gauges(f: &mut Frame, app: &AppState, area: Rect) {
     let load_ratio = (load_val / max_load).min(1.0);
     let soc_ratio = (battery_soc / 100.0).min(1.0);
 
-    let solar_label = format!("☀️ {:.1} kW", solar_val / 1000.0);
-    let load_label = format!("🏠 {:.1} kW ({:.1}%)", load_val / 1000.0, load_ratio_pct);
+    let solar_label = format!(
+        "☀️ {}",
+        or_unavailable(latest, "solar", format!("{:.1} kW", solar_val / 1000.0))
+    );
+    let load_label = format!(
+        "🏠 {}",
+        or_unavailable(
+            latest,
+            "load",
+            format!("{:.1} kW ({:.1}%)", load_val / 1000.0, load_ratio_pct)
+        )
+    );
 
-    // SOC label with optional remaining capacity
-    let soc_label = if let Some(capacity_kwh) = app.config.home_assistant.battery_capacity_kwh {
+    // SOC label with optional remaining capacity and time to full/empty
+    let soc_text = if latest.is_some_and(|d| d.battery_soc_estimated) {
+        format!("{:.0}% estimated", battery_soc)
+    } else {
+        format!("{:.0}%", battery_soc)
+    };
+    let soc_label = if is_unavailable(latest, "battery_soc") {
+        format!("🔋 {}", UNAVAILABLE)
+    } else if let Some(text) = missing_soc(app, latest) {
+        format!("🔋 {}", text)
+    } else if let Some(capacity_kwh) = app.config.home_assistant.battery_capacity_kwh {
         let remaining_kwh = (battery_soc / 100.0) * capacity_kwh;
-        format!("🔋 {:.0}% ({:.2} kWh)", battery_soc, remaining_kwh)
+        match latest.and_then(|d| battery_eta(app, d)) {
+            Some(eta) => format!("🔋 {} ({:.2} kWh) · {}", soc_text, remaining_kwh, eta),
+            None => format!("🔋 {} ({:.2} kWh)", soc_text, remaining_kwh),
+        }
     } else {
-        format!("🔋 {:.0}%", battery_soc)
+        format!("🔋 {}", soc_text)
     };
 
-    let solar_text_color = get_solar_text_color(solar_ratio); // Dynamic text color
+    // Dynamic text color, or the warning color when unavailable (the gauge is then empty)
+    let text_color = |metric: &str, color: Color| {
+        if is_unavailable(latest, metric) {
+            UNAVAILABLE_COLOR
+        } else {
+            color
+        }
+    };
+    let solar_text_color = text_color("solar", get_solar_text_color(solar_ratio));
 
     let solar_gauge = Gauge::default()
         .block(
@@ -245,7 +389,7 @@ fn render_main_gauges(f: &mut Frame, app: &AppState, area: Rect) {
         ))
         .ratio(solar_ratio);
 
-    let load_text_color = get_load_text_color(load_ratio); // Dynamic text color
+    let load_text_color = text_color("load", get_load_text_color(load_ratio));
 
     let load_gauge = Gauge::default()
         .block(
@@ -275,7 +419,7 @@ fn render_main_gauges(f: &mut Frame, app: &AppState, area: Rect) {
         .ratio(load_ratio);
 
     let soc_color = get_soc_gradient_color(soc_ratio);
-    let soc_text_color = get_soc_text_color(soc_ratio); // Dynamic text color
+    let soc_text_color = text_color("battery_soc", get_soc_text_color(soc_ratio));
 
     let soc_gauge = Gauge::default()
         .block(
@@ -304,26 +448,17 @@ fn render_main_gauges(f: &mut Frame, app: &AppState, area: Rect) {
 }
 
 fn render_charts(f: &mut Frame, app: &AppState, area: Rect) {
-    let solar_data: Vec<(f64, f64)> = app
-        .get_solar_history()
-        .iter()
-        .enumerate()
-        .map(|(i, &v)| (i as f64, v / 1000.0)) // Convert to kW
-        .collect();
-
-    let load_data: Vec<(f64, f64)> = app
-        .get_load_history()
-        .iter()
-        .enumerate()
-        .map(|(i, &v)| (i as f64, v / 1000.0)) // Convert to kW
-        .collect();
-
-    let battery_data: Vec<(f64, f64)> = app
-        .history
-        .iter()
-        .enumerate()
-        .map(|(i, d)| (i as f64, d.battery_power / 1000.0)) // Convert to kW
-        .collect();
+    // Unavailable samples are left out of the lines
+    let points = |history: Vec<Option<f64>>| -> Vec<(f64, f64)> {
+        history
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, v)| Some((i as f64, v? / 1000.0))) // Convert to kW
+            .collect()
+    };
+    let solar_data = points(app.get_solar_history());
+    let load_data = points(app.get_load_history());
+    let battery_data = points(app.get_battery_power_history());
 
     // Calculate dynamic Y-axis bounds with 2kW steps
     // Find min value from battery data (for discharge/negative values)
@@ -445,7 +580,238 @@ fn render_charts(f: &mut Frame, app: &AppState, area: Rect) {
     f.render_widget(legend, chunks[1]);
 }
 
-fn render_footer(f: &mut Frame, area: Rect) {
+/// Alert rows shown in the panel: active ones, then recently cleared
+const ALERTS_PANEL_ROWS: usize = 4;
+
+fn alerts_panel_height(app: &AppState) -> u16 {
+    let rows = panel_alerts(&app.alert_list).len();
+    if rows == 0
+        || !app
+            .alert_list
+            .iter()
+            .any(|a| a.is_active() && !a.is_silenced())
+    {
+        return 0;
+    }
+    rows as u16 + 2
+}
+
+/// Active alerts that still ask for attention, then recently cleared ones
+fn panel_alerts(alerts: &[Alert]) -> Vec<&Alert> {
+    alerts
+        .iter()
+        .filter(|a| !a.is_silenced() || !a.is_active())
+        .take(ALERTS_PANEL_ROWS)
+        .collect()
+}
+
+fn render_alerts_panel(f: &mut Frame, app: &AppState, area: Rect) {
+    if area.height == 0 {
+        return;
+    }
+    let alerts = panel_alerts(&app.alert_list);
+    let active = alerts.iter().filter(|a| a.is_active()).count();
+    let border_color = alerts
+        .first()
+        .map_or(Color::Rgb(255, 165, 0), |a| severity_color(a.severity));
+    let block = Block::default()
+        .title(format!(
+            " ALERTS ({} active, page {} to manage) ",
+            active,
+            Page::ALL.len()
+        ))
+        .title_style(
+            Style::default()
+                .fg(border_color)
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(border_color));
+
+    let rows = alerts.iter().map(|alert| {
+        let (state, color) = match &alert.cleared_at {
+            None => (
+                format!("since {}", format_clock(&alert.raised_at)),
+                severity_color(alert.severity),
+            ),
+            Some(cleared_at) => (
+                format!(
+                    "{} - {} cleared",
+                    format_clock(&alert.raised_at),
+                    format_clock(cleared_at)
+                ),
+                Color::Rgb(120, 120, 120),
+            ),
+        };
+        Row::new(vec![
+            format!("  {:?}", alert.severity).to_uppercase(),
+            alert.name.clone(),
+            format!(
+                "{:.1} {} {:.1}",
+                alert.value,
+                alert.comparison.symbol(),
+                alert.threshold
+            ),
+            state,
+        ])
+        .style(Style::default().fg(color))
+    });
+    let table = Table::new(
+        rows,
+        &[
+            Constraint::Length(11),
+            Constraint::Min(20),
+            Constraint::Length(22),
+            Constraint::Length(22),
+        ],
+    )
+    .block(block)
+    .column_spacing(2);
+    f.render_widget(table, area);
+}
+
+fn render_alerts_page(f: &mut Frame, app: &AppState, area: Rect) {
+    let block = Block::default()
+        .title(" ALERTS ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(255, 165, 0))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(200, 130, 0)))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let alerts = &app.alert_list;
+    if alerts.is_empty() {
+        let hint = Paragraph::new("No alerts raised yet")
+            .style(Style::default().fg(Color::Rgb(120, 120, 120)));
+        f.render_widget(hint, sections[0]);
+        return;
+    }
+
+    let header = Row::new(vec![
+        "Severity", "Alert", "Value", "Raised", "Cleared", "Duration", "Status",
+    ])
+    .style(Style::default().fg(Color::Rgb(150, 150, 150)));
+    let now = chrono::Utc::now();
+    let rows = alerts.iter().map(|alert| {
+        let raised = chrono::DateTime::parse_from_rfc3339(&alert.raised_at).ok();
+        let cleared = alert
+            .cleared_at
+            .as_deref()
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok());
+        let duration = raised.map(|raised| {
+            let end = cleared.map_or(now, |c| c.with_timezone(&chrono::Utc));
+            format_duration(
+                (end - raised.with_timezone(&chrono::Utc))
+                    .num_seconds()
+                    .max(0) as f64,
+            )
+        });
+        let snoozed_until = alert
+            .snoozed_until
+            .as_deref()
+            .filter(|_| alert.is_snoozed())
+            .map(format_clock);
+        let status = match (alert.is_active(), alert.acknowledged, snoozed_until) {
+            (_, _, Some(until)) => format!("Snoozed until {}", until),
+            (true, true, None) => "Acknowledged".to_string(),
+            (true, false, None) => "Active".to_string(),
+            (false, _, None) => "Cleared".to_string(),
+        };
+        let color = if alert.is_active() && !alert.is_silenced() {
+            severity_color(alert.severity)
+        } else if alert.is_active() {
+            Color::White
+        } else {
+            Color::Rgb(120, 120, 120)
+        };
+        let date_time = |t: Option<chrono::DateTime<chrono::FixedOffset>>| {
+            t.map_or("-".to_string(), |t| t.format("%m-%d %H:%M").to_string())
+        };
+
+        Row::new(vec![
+            format!("{:?}", alert.severity).to_uppercase(),
+            alert.name.clone(),
+            format!(
+                "{:.1} {} {:.1}",
+                alert.value,
+                alert.comparison.symbol(),
+                alert.threshold
+            ),
+            date_time(raised),
+            date_time(cleared),
+            duration.unwrap_or_else(|| "-".to_string()),
+            status,
+        ])
+        .style(Style::default().fg(color))
+    });
+    let table = Table::new(
+        rows,
+        &[
+            Constraint::Length(9),
+            Constraint::Min(16),
+            Constraint::Length(20),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(9),
+            Constraint::Length(20),
+        ],
+    )
+    .header(header)
+    .highlight_style(Style::default().bg(Color::Rgb(50, 50, 70)))
+    .highlight_symbol("▶ ")
+    .column_spacing(2);
+    let mut table_state = TableState::default();
+    table_state.select(Some(app.alert_selected.min(alerts.len() - 1)));
+    f.render_stateful_widget(table, sections[0], &mut table_state);
+
+    let keys = Paragraph::new(
+        "↑/↓ select  ·  a acknowledge  ·  s snooze (15m → 1h → 4h → 24h)  ·  u unsnooze",
+    )
+    .style(Style::default().fg(Color::Rgb(150, 150, 150)));
+    f.render_widget(keys, sections[1]);
+}
+
+fn render_footer(f: &mut Frame, app: &AppState, area: Rect) {
+    if let Some(status) = app.current_status() {
+        let color = if status.ok {
+            Color::Rgb(100, 220, 100)
+        } else {
+            Color::Rgb(255, 100, 100)
+        };
+        let footer = Paragraph::new(Span::styled(
+            status.text.clone(),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ))
+        .alignment(Alignment::Center);
+        f.render_widget(footer, area);
+        return;
+    }
+
+    let hint = overnight_warning(app)
+        .map(|warning| (warning, Color::Red))
+        .or_else(|| surplus_hint(app).map(|hint| (hint, Color::Rgb(100, 255, 100))));
+    if let Some((text, color)) = hint {
+        let footer = Paragraph::new(Span::styled(
+            text,
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ))
+        .alignment(Alignment::Center);
+        f.render_widget(footer, area);
+        return;
+    }
+
     let footer = Paragraph::new(Line::from(vec![
         Span::styled(" Press ", Style::default().fg(Color::Rgb(150, 150, 150))),
         Span::styled(
@@ -454,8 +820,25 @@ fn render_footer(f: &mut Frame, area: Rect) {
                 .fg(Color::Rgb(255, 100, 100)) // Light red
                 .add_modifier(Modifier::BOLD),
         ),
+        Span::styled(" to quit, ", Style::default().fg(Color::Rgb(150, 150, 150))),
+        Span::styled(
+            "Tab",
+            Style::default()
+                .fg(Color::Rgb(100, 200, 255)) // Light blue
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            " to switch page, ",
+            Style::default().fg(Color::Rgb(150, 150, 150)),
+        ),
+        Span::styled(
+            "x",
+            Style::default()
+                .fg(Color::Rgb(100, 200, 255)) // Light blue
+                .add_modifier(Modifier::BOLD),
+        ),
         Span::styled(
-            " to quit  |  ",
+            " for actions  |  ",
             Style::default().fg(Color::Rgb(150, 150, 150)),
         ),
         Span::styled(
@@ -501,7 +884,7 @@ fn render_combined_tables(f: &mut Frame, app: &AppState, area: Rect) {
     // Bottom row: max(LIFETIME 1 row, SYSTEM STATUS 11 rows) + borders(2) = 13 lines
     // Both cards in the same horizontal row get equal height
 
-    // Left column: DAILY ENERGY TOTALS (top) + LIFETIME & ADDITIONAL (bottom)
+    // Left column: DAILY ENERGY TOTALS (top) + LIFETIME & ADDITIONAL + SELF-SUFFICIENCY (bottom)
     let left_sections = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Length(8), Constraint::Min(13)])
@@ -517,7 +900,13 @@ fn render_combined_tables(f: &mut Frame, app: &AppState, area: Rect) {
 
     // Render all four sections
     render_daily_energy_compact(f, app, left_sections[0]);
-    render_reserved_space(f, app, left_sections[1]);
+    let statistics_sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(7), Constraint::Length(6)])
+        .margin(0)
+        .split(left_sections[1]);
+    render_reserved_space(f, app, statistics_sections[0]);
+    render_self_sufficiency(f, app, statistics_sections[1]);
     render_realtime_power(f, app, right_sections[0]);
     render_system_status(f, app, right_sections[1]);
 }
@@ -530,13 +919,13 @@ fn render_realtime_power(f: &mut Frame, app: &AppState, area: Rect) {
     let battery_power = latest.map(|d| d.battery_power).unwrap_or(0.0);
     let battery_voltage = latest.map(|d| d.battery_voltage).unwrap_or(0.0);
 
-    let solar_trend = get_power_trend(&app.history, |d| d.solar);
-    let load_trend = get_power_trend(&app.history, |d| d.load);
-    let grid_import_trend = get_power_trend(&app.history, |d| d.grid_import);
-    let grid_export_trend = get_power_trend(&app.history, |d| d.grid_export);
+    let solar_trend = get_power_trend(&app.history, "solar", |d| d.solar);
+    let load_trend = get_power_trend(&app.history, "load", |d| d.load);
+    let grid_import_trend = get_power_trend(&app.history, "grid_import", |d| d.grid_import);
+    let grid_export_trend = get_power_trend(&app.history, "grid_export", |d| d.grid_export);
 
     // Check if battery is floating
-    let is_floating = is_battery_floating(app, battery_voltage, battery_power);
+    let is_floating = app.is_battery_floating(battery_voltage, battery_power);
 
     let solar_val_str = format!("{:.2} kW", solar_val(latest) / 1000.0);
     let load_val_str = format!("{:.2} kW", load_val(latest) / 1000.0);
@@ -547,31 +936,35 @@ fn render_realtime_power(f: &mut Frame, app: &AppState, area: Rect) {
     let main_table = Table::new(
         vec![
             Row::new(vec![
-                "  Solar Production",
-                solar_val_str.as_str(),
-                solar_trend,
+                Span::raw("  Solar Production"),
+                metric_span(latest, "solar", solar_val_str),
+                Span::raw(solar_trend),
             ])
             .style(Style::default().fg(Color::White)),
             Row::new(vec![
-                "  Load Consumption",
-                load_val_str.as_str(),
-                load_trend,
+                Span::raw("  Load Consumption"),
+                metric_span(latest, "load", load_val_str),
+                Span::raw(load_trend),
             ])
             .style(Style::default().fg(Color::White)),
             Row::new(vec![
-                "  Grid Import",
-                grid_import_str.as_str(),
-                grid_import_trend,
+                Span::raw("  Grid Import"),
+                metric_span(latest, "grid_import", grid_import_str),
+                Span::raw(grid_import_trend),
             ])
             .style(Style::default().fg(Color::White)),
             Row::new(vec![
-                "  Grid Export",
-                grid_export_str.as_str(),
-                grid_export_trend,
+                Span::raw("  Grid Export"),
+                metric_span(latest, "grid_export", grid_export_str),
+                Span::raw(grid_export_trend),
+            ])
+            .style(Style::default().fg(Color::White)),
+            Row::new(vec![
+                Span::raw("  Battery Power"),
+                metric_span(latest, "battery_power", battery_power_str),
+                Span::raw("→"),
             ])
             .style(Style::default().fg(Color::White)),
-            Row::new(vec!["  Battery Power", battery_power_str.as_str(), "→"])
-                .style(Style::default().fg(Color::White)),
         ],
         &[
             Constraint::Percentage(40),
@@ -627,9 +1020,16 @@ fn render_system_status(f: &mut Frame, app: &AppState, area: Rect) {
     } else {
         format!("{:.1}%", battery_soc_value)
     };
+    let battery_soc_str = if let Some(text) = missing_soc(app, latest) {
+        text.to_string()
+    } else if latest.is_some_and(|d| d.battery_soc_estimated) {
+        format!("~{}", battery_soc_str) // Estimated
+    } else {
+        or_unavailable(latest, "battery_soc", battery_soc_str)
+    };
 
     // Check if battery is floating
-    let is_floating = is_battery_floating(app, battery_voltage, battery_power);
+    let is_floating = app.is_battery_floating(battery_voltage, battery_power);
 
     let battery_voltage_str = if is_floating {
         format!("{:.1} V 🟢FLOAT", battery_voltage)
@@ -637,48 +1037,37 @@ fn render_system_status(f: &mut Frame, app: &AppState, area: Rect) {
         format!("{:.1} V", battery_voltage)
     };
 
-    let battery_current_str = format!("{:.1} A", battery_current);
-    let load_current_str = format!("{:.1} A", load_current);
-    let load_power_str = format!("{:.2} kW", load_val / 1000.0);
-    let load_power_factor_str = format!("{:.2}", load_power_factor);
-    let grid_power_factor_str = format!("{:.2}", grid_power_factor);
-    let battery_temp_str = format!("{:.1} °C", battery_temp);
-    let inverter_temp_str = format!("{:.1} °C", inverter_temp);
-    let inverter_voltage_str = format!("{:.1} V", inverter_voltage);
-    let grid_voltage_str = format!("{:.1} V", grid_voltage);
-    let inverter_freq_str = format!("{:.1} Hz", inverter_freq);
-    let dc_trans_temp_str = format!("{:.1} °C", dc_trans_temp);
-    let radiator_temp_str = format!("{:.1} °C", radiator_temp);
-
-    // Helper function to get temperature color (white for normal, only warn on high temps)
-    let get_temp_color = |temp: f64| -> Color {
-        if temp >= 35.0 {
-            Color::Rgb(255, 165, 0) // Orange for high temp
-        } else {
-            Color::White // White for normal temps
-        }
-    };
-
-    // Helper function to get AC voltage color (white for normal, only warn on low voltage)
-    let get_voltage_color = |voltage: f64| -> Color {
-        if voltage < 210.0 {
-            Color::Red // Red for critical low
-        } else if voltage < 220.0 {
-            Color::Rgb(255, 165, 0) // Orange for low voltage
-        } else {
-            Color::White // White for normal voltage
+    let battery_eta_str = latest
+        .and_then(|d| battery_eta(app, d))
+        .unwrap_or_else(|| "-".to_string());
+    let value = |metric: &str, text: String| or_unavailable(latest, metric, text);
+    let battery_voltage_str = value("battery_voltage", battery_voltage_str);
+    let battery_current_str = value("battery_current", format!("{:.1} A", battery_current));
+    let load_current_str = value("load_current", format!("{:.1} A", load_current));
+    let load_power_str = value("load", format!("{:.2} kW", load_val / 1000.0));
+    let load_power_factor_str = value("load_power_factor", format!("{:.2}", load_power_factor));
+    let grid_power_factor_str = value("grid_power_factor", format!("{:.2}", grid_power_factor));
+    let battery_temp_str = value("battery_temp", format!("{:.1} °C", battery_temp));
+    let inverter_temp_str = value("inverter_temp", format!("{:.1} °C", inverter_temp));
+    let inverter_voltage_str = value("inverter_voltage", format!("{:.1} V", inverter_voltage));
+    let grid_voltage_str = value("grid_voltage", format!("{:.1} V", grid_voltage));
+    let inverter_freq_str = value("inverter_frequency", format!("{:.1} Hz", inverter_freq));
+    let dc_trans_temp_str = value("dc_transformer_temp", format!("{:.1} °C", dc_trans_temp));
+    let radiator_temp_str = value("radiator_temp", format!("{:.1} °C", radiator_temp));
+
+    // Readings with an active alert take its severity color, unavailable ones the warning
+    // color (white otherwise)
+    let metric_color = |metric: &str| {
+        if is_unavailable(latest, metric) {
+            return UNAVAILABLE_COLOR;
         }
+        severity_color(latest.map_or(Severity::Normal, |d| alerts::severity_of(d, metric)))
     };
-
-    // Helper function to get current color (white for normal, warn on high current)
-    let get_current_color = |current: f64| -> Color {
-        if current >= 100.0 {
-            Color::Red // Red for very high current
-        } else if current >= 80.0 {
-            Color::Rgb(255, 165, 0) // Orange for high current
-        } else {
-            Color::White // White for normal current
-        }
+    let cell = |label: &'static str, text: &str, metric: &str| {
+        Row::new(vec![
+            Span::styled(label, Style::default().fg(Color::White)),
+            Span::styled(text.to_string(), Style::default().fg(metric_color(metric))),
+        ])
     };
 
     // Split area into 2x2 grid
@@ -700,19 +1089,12 @@ fn render_system_status(f: &mut Frame, app: &AppState, area: Rect) {
     // Top-left: BATTERY
     let battery_table = Table::new(
         vec![
-            Row::new(vec!["  SOC", battery_soc_str.as_str()])
-                .style(Style::default().fg(Color::White)),
-            Row::new(vec!["  Voltage", battery_voltage_str.as_str()])
+            cell("  SOC", &battery_soc_str, "battery_soc"),
+            Row::new(vec!["  ETA", battery_eta_str.as_str()])
                 .style(Style::default().fg(Color::White)),
-            Row::new(vec!["  Current", battery_current_str.as_str()])
-                .style(Style::default().fg(Color::White)),
-            Row::new(vec![
-                Span::styled("  Temperature", Style::default().fg(Color::White)),
-                Span::styled(
-                    &battery_temp_str,
-                    Style::default().fg(get_temp_color(battery_temp)),
-                ),
-            ]),
+            cell("  Voltage", &battery_voltage_str, "battery_voltage"),
+            cell("  Current", &battery_current_str, "battery_current"),
+            cell("  Temperature", &battery_temp_str, "battery_temp"),
         ],
         &[Constraint::Length(14), Constraint::Min(0)],
     )
@@ -733,24 +1115,14 @@ fn render_system_status(f: &mut Frame, app: &AppState, area: Rect) {
     // Top-right: LOAD
     let load_table = Table::new(
         vec![
-            Row::new(vec!["  Power", load_power_str.as_str()])
-                .style(Style::default().fg(Color::White)),
-            Row::new(vec![
-                Span::styled("  Voltage", Style::default().fg(Color::White)),
-                Span::styled(
-                    &inverter_voltage_str,
-                    Style::default().fg(get_voltage_color(inverter_voltage)),
-                ),
-            ]),
-            Row::new(vec![
-                Span::styled("  Current", Style::default().fg(Color::White)),
-                Span::styled(
-                    &load_current_str,
-                    Style::default().fg(get_current_color(load_current)),
-                ),
-            ]),
-            Row::new(vec!["  Power Factor", load_power_factor_str.as_str()])
-                .style(Style::default().fg(Color::White)),
+            cell("  Power", &load_power_str, "load"),
+            cell("  Voltage", &inverter_voltage_str, "inverter_voltage"),
+            cell("  Current", &load_current_str, "load_current"),
+            cell(
+                "  Power Factor",
+                &load_power_factor_str,
+                "load_power_factor",
+            ),
         ],
         &[Constraint::Length(15), Constraint::Min(0)],
     )
@@ -771,17 +1143,13 @@ fn render_system_status(f: &mut Frame, app: &AppState, area: Rect) {
     // Bottom-left: GRID
     let grid_table = Table::new(
         vec![
-            Row::new(vec![
-                Span::styled("  Voltage", Style::default().fg(Color::White)),
-                Span::styled(
-                    &grid_voltage_str,
-                    Style::default().fg(get_voltage_color(grid_voltage)),
-                ),
-            ]),
-            Row::new(vec!["  Frequency", inverter_freq_str.as_str()])
-                .style(Style::default().fg(Color::White)),
-            Row::new(vec!["  Power Factor", grid_power_factor_str.as_str()])
-                .style(Style::default().fg(Color::White)),
+            cell("  Voltage", &grid_voltage_str, "grid_voltage"),
+            cell("  Frequency", &inverter_freq_str, "inverter_frequency"),
+            cell(
+                "  Power Factor",
+                &grid_power_factor_str,
+                "grid_power_factor",
+            ),
         ],
         &[Constraint::Length(15), Constraint::Min(0)],
     )
@@ -804,27 +1172,9 @@ fn render_system_status(f: &mut Frame, app: &AppState, area: Rect) {
         vec![
             Row::new(vec!["  Status", inverter_status.as_str()])
                 .style(Style::default().fg(Color::White)),
-            Row::new(vec![
-                Span::styled("  Temperature", Style::default().fg(Color::White)),
-                Span::styled(
-                    &inverter_temp_str,
-                    Style::default().fg(get_temp_color(inverter_temp)),
-                ),
-            ]),
-            Row::new(vec![
-                Span::styled("  AC Temp", Style::default().fg(Color::White)),
-                Span::styled(
-                    &radiator_temp_str,
-                    Style::default().fg(get_temp_color(radiator_temp)),
-                ),
-            ]),
-            Row::new(vec![
-                Span::styled("  DC Combiner", Style::default().fg(Color::White)),
-                Span::styled(
-                    &dc_trans_temp_str,
-                    Style::default().fg(get_temp_color(dc_trans_temp)),
-                ),
-            ]),
+            cell("  Temperature", &inverter_temp_str, "inverter_temp"),
+            cell("  AC Temp", &radiator_temp_str, "radiator_temp"),
+            cell("  DC Combiner", &dc_trans_temp_str, "dc_transformer_temp"),
         ],
         &[Constraint::Length(14), Constraint::Min(0)],
     )
@@ -862,8 +1212,13 @@ fn render_daily_energy_compact(f: &mut Frame, app: &AppState, area: Rect) {
     // Configurable scale from config, default to 100 kWh
     let max_scale = app.config.home_assistant.max_daily_energy.unwrap_or(100.0);
 
+    let title = if latest.is_some_and(|d| !d.computed_daily.is_empty()) {
+        " DAILY ENERGY TOTALS (≈ computed locally) "
+    } else {
+        " DAILY ENERGY TOTALS "
+    };
     let block = Block::default()
-        .title(" DAILY ENERGY TOTALS ")
+        .title(title)
         .title_style(
             Style::default()
                 .fg(Color::Rgb(255, 150, 255))
@@ -879,19 +1234,45 @@ fn render_daily_energy_compact(f: &mut Frame, app: &AppState, area: Rect) {
     // Render each bar
     let bar_height = 1;
     let items = [
-        ("PV Generation", day_pv, Color::Rgb(255, 215, 0)),
-        ("Load Consumption", day_load, Color::Rgb(138, 161, 255)),
-        ("Battery Charge", day_bat_charge, Color::Rgb(100, 255, 100)),
+        (
+            "PV Generation",
+            "day_pv_energy",
+            day_pv,
+            Color::Rgb(255, 215, 0),
+        ),
+        (
+            "Load Consumption",
+            "day_load_energy",
+            day_load,
+            Color::Rgb(138, 161, 255),
+        ),
+        (
+            "Battery Charge",
+            "day_battery_charge",
+            day_bat_charge,
+            Color::Rgb(100, 255, 100),
+        ),
         (
             "Battery Discharge",
+            "day_battery_discharge",
             day_bat_discharge,
             Color::Rgb(255, 165, 0),
         ),
-        ("Grid Import", day_grid_import, Color::Rgb(255, 100, 100)),
-        ("Grid Export", day_grid_export, Color::Rgb(100, 200, 255)),
+        (
+            "Grid Import",
+            "day_grid_import",
+            day_grid_import,
+            Color::Rgb(255, 100, 100),
+        ),
+        (
+            "Grid Export",
+            "day_grid_export",
+            day_grid_export,
+            Color::Rgb(100, 200, 255),
+        ),
     ];
 
-    for (idx, (label, value, color)) in items.iter().enumerate() {
+    for (idx, (label, field, value, color)) in items.iter().enumerate() {
         let y = inner.y + idx as u16;
         if y >= inner.y + inner.height {
             break;
@@ -905,7 +1286,25 @@ fn render_daily_energy_compact(f: &mut Frame, app: &AppState, area: Rect) {
         // Scale bar based on configurable max_daily_energy range
         let normalized_value = (value / max_scale).min(1.0); // Cap at 100%
         let filled_bar_width = (normalized_value * available_width as f64) as u16;
-        let value_str = format!("{:>6.2} kWh", value);
+        // "≈" marks values integrated locally because the sensor is not configured
+        let computed = latest.is_some_and(|d| d.computed_daily.iter().any(|f| f == field));
+        let unavailable = is_unavailable(latest, field);
+        let value_str = if unavailable {
+            format!(" {:>6}", UNAVAILABLE)
+        } else if computed {
+            format!("≈{:>6.2} kWh", value)
+        } else {
+            format!(" {:>6.2} kWh", value)
+        };
+        let value_style = if unavailable {
+            Style::default().fg(UNAVAILABLE_COLOR)
+        } else if computed {
+            Style::default()
+                .fg(Color::Rgb(180, 180, 180))
+                .add_modifier(Modifier::ITALIC)
+        } else {
+            Style::default().fg(Color::White)
+        };
 
         // Render label (right-aligned)
         let label_area = Rect::new(inner.x, y, label_width, bar_height);
@@ -915,8 +1314,7 @@ fn render_daily_energy_compact(f: &mut Frame, app: &AppState, area: Rect) {
 
         // Render value
         let value_area = Rect::new(inner.x + label_width + 1, y, value_width, bar_height);
-        let value_text =
-            Paragraph::new(value_str.to_string()).style(Style::default().fg(Color::White));
+        let value_text = Paragraph::new(value_str.to_string()).style(value_style);
         f.render_widget(value_text, value_area);
 
         // Render background bar (full scale, low opacity - using darker version of color)
@@ -1158,41 +1556,1115 @@ fn render_reserved_space(f: &mut Frame, app: &AppState, area: Rect) {
     f.render_widget(table, area);
 }
 
-fn solar_val(latest: Option<&crate::state::PowerData>) -> f64 {
-    latest.map(|d| d.solar).unwrap_or(0.0)
-}
-
-fn load_val(latest: Option<&crate::state::PowerData>) -> f64 {
-    latest.map(|d| d.load).unwrap_or(0.0)
-}
-
-fn battery_soc(latest: Option<&crate::state::PowerData>) -> f64 {
-    latest.map(|d| d.battery_soc).unwrap_or(0.0)
-}
+fn render_self_sufficiency(f: &mut Frame, app: &AppState, area: Rect) {
+    let latest = app.history.last();
 
-fn is_battery_floating(app: &AppState, battery_voltage: f64, battery_power: f64) -> bool {
-    let float_voltage_threshold = app
-        .config
-        .home_assistant
-        .battery_float_voltage
-        .unwrap_or(54.0);
-    let voltage_tolerance = 0.5;
+    let live_consumption = latest.map(|d| d.self_consumption).unwrap_or(0.0);
+    let live_sufficiency = latest.map(|d| d.self_sufficiency).unwrap_or(0.0);
+    let day_consumption = latest.map(|d| d.day_self_consumption).unwrap_or(0.0);
+    let day_sufficiency = latest.map(|d| d.day_self_sufficiency).unwrap_or(0.0);
 
-    (battery_voltage >= float_voltage_threshold - voltage_tolerance)
-        && (battery_voltage <= float_voltage_threshold + voltage_tolerance)
-        && battery_power.abs() < 50.0
-}
+    let percent_cell = |metric: &str, value: f64| {
+        if is_unavailable(latest, metric) {
+            return Span::styled(
+                format!("{:>6}", UNAVAILABLE),
+                Style::default().fg(UNAVAILABLE_COLOR),
+            );
+        }
+        Span::styled(
+            format!("{:>5.1}%", value),
+            Style::default().fg(get_soc_gradient_color(value / 100.0)),
+        )
+    };
 
-fn get_power_trend<F>(history: &[crate::state::PowerData], get_value: F) -> &'static str
-where
-    F: Fn(&crate::state::PowerData) -> f64,
-{
-    if history.len() < 2 {
+    let block = Block::default()
+        .title(" SELF-SUFFICIENCY ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(100, 255, 100))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(50, 180, 50)))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(inner);
+
+    let table = Table::new(
+        vec![
+            Row::new(vec![
+                Line::from(""),
+                Line::from("Live"),
+                Line::from("Today"),
+            ])
+            .style(Style::default().fg(Color::Rgb(150, 150, 150))),
+            Row::new(vec![
+                Line::from("Self-Consumption"),
+                Line::from(percent_cell("self_consumption", live_consumption)),
+                Line::from(percent_cell("day_self_consumption", day_consumption)),
+            ])
+            .style(Style::default().fg(Color::White)),
+            Row::new(vec![
+                Line::from("Self-Sufficiency"),
+                Line::from(percent_cell("self_sufficiency", live_sufficiency)),
+                Line::from(percent_cell("day_self_sufficiency", day_sufficiency)),
+            ])
+            .style(Style::default().fg(Color::White)),
+        ],
+        &[
+            Constraint::Length(18),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .column_spacing(2);
+    f.render_widget(table, rows[0]);
+
+    // Live self-sufficiency over the history window
+    if rows[1].height > 0 {
+        let history: Vec<u64> = app
+            .history
+            .iter()
+            .filter(|d| d.is_available("self_sufficiency"))
+            .map(|d| d.self_sufficiency.round() as u64)
+            .collect();
+        let visible = &history[history.len().saturating_sub(rows[1].width as usize)..];
+        let sparkline = Sparkline::default()
+            .data(visible)
+            .max(100)
+            .style(Style::default().fg(Color::Rgb(100, 255, 100)));
+        f.render_widget(sparkline, rows[1]);
+    }
+}
+
+/// One edge of the power-flow diagram, drawn from `path[0]` towards the hub
+struct FlowEdge {
+    path: Vec<(u16, u16)>,
+    vertical: bool,
+    watts: f64,          // Magnitude of the flow
+    towards_hub: bool,   // Direction of the flow along the path
+    color: (u8, u8, u8), // Node color at full intensity
+}
+
+fn render_power_flow(f: &mut Frame, app: &AppState, area: Rect) {
+    let latest = app.history.last();
+
+    let solar = latest.map(|d| d.solar).unwrap_or(0.0);
+    let load = latest.map(|d| d.load).unwrap_or(0.0);
+    let grid_import = latest.map(|d| d.grid_import).unwrap_or(0.0);
+    let grid_export = latest.map(|d| d.grid_export).unwrap_or(0.0);
+    let battery_power = latest.map(|d| d.battery_power).unwrap_or(0.0);
+    let battery_voltage = latest.map(|d| d.battery_voltage).unwrap_or(0.0);
+    let battery_soc_value = battery_soc(latest);
+    let is_floating = app.is_battery_floating(battery_voltage, battery_power);
+
+    // Net grid flow: positive = importing, negative = exporting
+    let grid_net = grid_import - grid_export;
+
+    let block = Block::default()
+        .title(" POWER FLOW ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(100, 200, 255))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(0, 180, 220)));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    const NODE_WIDTH: u16 = 26;
+    const NODE_HEIGHT: u16 = 4;
+
+    if inner.width < NODE_WIDTH * 2 + 8 || inner.height < NODE_HEIGHT * 2 + 3 {
+        return;
+    }
+
+    let cx = inner.x + inner.width / 2;
+    let cy = inner.y + inner.height / 2;
+
+    let pv_area = Rect::new(cx - NODE_WIDTH / 2, inner.y, NODE_WIDTH, NODE_HEIGHT);
+    let home_area = Rect::new(
+        cx - NODE_WIDTH / 2,
+        inner.y + inner.height - NODE_HEIGHT,
+        NODE_WIDTH,
+        NODE_HEIGHT,
+    );
+    let grid_area = Rect::new(inner.x, cy - NODE_HEIGHT / 2, NODE_WIDTH, NODE_HEIGHT);
+    let battery_area = Rect::new(
+        inner.x + inner.width - NODE_WIDTH,
+        cy - NODE_HEIGHT / 2,
+        NODE_WIDTH,
+        NODE_HEIGHT,
+    );
+
+    let grid_unavailable =
+        is_unavailable(latest, "grid_import") || is_unavailable(latest, "grid_export");
+    let grid_text = if grid_unavailable {
+        UNAVAILABLE.to_string()
+    } else if grid_net > 10.0 {
+        format!("Import {:.2} kW", grid_net / 1000.0)
+    } else if grid_net < -10.0 {
+        format!("Export {:.2} kW", -grid_net / 1000.0)
+    } else {
+        "Idle".to_string()
+    };
+    let soc_text = match missing_soc(app, latest) {
+        Some(text) => text.to_string(),
+        None => or_unavailable(latest, "battery_soc", format!("{:.0}%", battery_soc_value)),
+    };
+    let battery_unavailable =
+        is_unavailable(latest, "battery_power") || is_unavailable(latest, "battery_soc");
+    let battery_text = if is_unavailable(latest, "battery_power") {
+        format!("{}  {}", soc_text, UNAVAILABLE)
+    } else {
+        match BatteryState::from_power(battery_power, is_floating) {
+            BatteryState::Charging => format!("{}  +{:.2} kW", soc_text, battery_power / 1000.0),
+            BatteryState::Discharging => {
+                format!("{}  -{:.2} kW", soc_text, -battery_power / 1000.0)
+            }
+            BatteryState::Floating => format!("{}  Floating", soc_text),
+            BatteryState::Idle => format!("{}  Idle", soc_text),
+        }
+    };
+
+    let edges = [
+        FlowEdge {
+            path: (pv_area.bottom()..cy).map(|y| (cx, y)).collect(),
+            vertical: true,
+            watts: solar,
+            towards_hub: true,
+            color: (255, 215, 0),
+        },
+        FlowEdge {
+            path: (cy + 1..home_area.y).rev().map(|y| (cx, y)).collect(),
+            vertical: true,
+            watts: load,
+            towards_hub: false,
+            color: (138, 161, 255),
+        },
+        FlowEdge {
+            path: (grid_area.right()..cx).map(|x| (x, cy)).collect(),
+            vertical: false,
+            watts: grid_net.abs(),
+            towards_hub: grid_net > 0.0,
+            color: (255, 100, 100),
+        },
+        FlowEdge {
+            path: (cx + 1..battery_area.x).rev().map(|x| (x, cy)).collect(),
+            vertical: false,
+            watts: battery_power.abs(),
+            towards_hub: battery_power < 0.0,
+            color: (100, 255, 100),
+        },
+    ];
+
+    let max_power = app.config.home_assistant.max_solar_power.unwrap_or(18000.0);
+    for edge in &edges {
+        render_flow_edge(f, edge, max_power);
+    }
+
+    // Hub where all flows meet
+    f.buffer_mut()[(cx, cy)].set_symbol("◆").set_style(
+        Style::default()
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    render_flow_node(
+        f,
+        pv_area,
+        "☀ PV",
+        metric_span(latest, "solar", format!("{:.2} kW", solar / 1000.0)),
+        Color::Rgb(255, 215, 0),
+    );
+    render_flow_node(
+        f,
+        home_area,
+        "🏠 HOME",
+        metric_span(latest, "load", format!("{:.2} kW", load / 1000.0)),
+        Color::Rgb(138, 161, 255),
+    );
+    render_flow_node(
+        f,
+        grid_area,
+        "⚡ GRID",
+        flow_value(grid_text, grid_unavailable),
+        Color::Rgb(255, 100, 100),
+    );
+    render_flow_node(
+        f,
+        battery_area,
+        "🔋 BATTERY",
+        flow_value(battery_text, battery_unavailable),
+        Color::Rgb(100, 255, 100),
+    );
+}
+
+/// Node value text, in the warning color when one of its readings is unavailable
+fn flow_value(text: String, unavailable: bool) -> Span<'static> {
+    if unavailable {
+        Span::styled(text, Style::default().fg(UNAVAILABLE_COLOR))
+    } else {
+        Span::raw(text)
+    }
+}
+
+fn render_flow_node(f: &mut Frame, area: Rect, title: &str, value: Span<'static>, color: Color) {
+    let node = Paragraph::new(vec![
+        Line::from(Span::styled(
+            title.to_string(),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(value).style(Style::default().fg(Color::White)),
+    ])
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(color)),
+    );
+    f.render_widget(node, area);
+}
+
+fn render_flow_edge(f: &mut Frame, edge: &FlowEdge, max_power: f64) {
+    // Below 10 W the edge is drawn as an idle, dim line without arrows
+    let active = edge.watts > 10.0;
+    let ratio = if max_power > 0.0 {
+        (edge.watts / max_power).min(1.0)
+    } else {
+        0.0
+    };
+
+    // Line weight by power tier
+    let line = match (edge.vertical, ratio) {
+        (true, r) if r < 0.1 => "│",
+        (true, r) if r < 0.4 => "┃",
+        (true, _) => "║",
+        (false, r) if r < 0.1 => "─",
+        (false, r) if r < 0.4 => "━",
+        (false, _) => "═",
+    };
+
+    let (r, g, b) = edge.color;
+    let color = if active {
+        apply_gradient(Color::Rgb(r, g, b), ratio.max(0.2))
+    } else {
+        Color::Rgb(60, 60, 60)
+    };
+
+    // Arrow marker direction in screen space; paths run towards the hub
+    let arrow = match (
+        edge.vertical,
+        edge.towards_hub,
+        edge.path.first(),
+        edge.path.last(),
+    ) {
+        (true, towards, Some(first), Some(last)) => {
+            if (last.1 > first.1) == towards {
+                "▼"
+            } else {
+                "▲"
+            }
+        }
+        (false, towards, Some(first), Some(last)) => {
+            if (last.0 > first.0) == towards {
+                "▶"
+            } else {
+                "◀"
+            }
+        }
+        _ => return,
+    };
+
+    // Arrows march along the edge; more power moves them faster
+    const SPACING: usize = 4;
+    let step_ms = (300.0 - 220.0 * ratio) as u128;
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let phase = ((now_ms / step_ms.max(1)) % SPACING as u128) as usize;
+
+    let len = edge.path.len();
+    for (idx, &(x, y)) in edge.path.iter().enumerate() {
+        // Index along the direction of flow
+        let along = if edge.towards_hub { idx } else { len - 1 - idx };
+        let symbol = if active && (along + SPACING - phase).is_multiple_of(SPACING) {
+            arrow
+        } else {
+            line
+        };
+        f.buffer_mut()[(x, y)]
+            .set_symbol(symbol)
+            .set_style(Style::default().fg(color));
+    }
+}
+
+fn render_energy_breakdown(f: &mut Frame, app: &AppState, area: Rect) {
+    let breakdown = app
+        .history
+        .last()
+        .map(EnergyBreakdown::from_daily)
+        .unwrap_or_default();
+
+    let block = Block::default()
+        .title(" TODAY'S ENERGY BREAKDOWN ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(255, 150, 255))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(200, 50, 200)))
+        .padding(Padding::new(2, 2, 1, 0));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(7), // Where PV went
+            Constraint::Length(1),
+            Constraint::Length(7), // Where load came from
+            Constraint::Min(0),
+        ])
+        .split(inner);
+
+    let home = Color::Rgb(138, 161, 255);
+    let battery = Color::Rgb(100, 255, 100);
+    let grid = Color::Rgb(255, 100, 100);
+    let solar = Color::Rgb(255, 215, 0);
+
+    render_energy_split(
+        f,
+        chunks[0],
+        ("☀ PV", solar),
+        breakdown.pv_total,
+        false,
+        &breakdown.pv_flows(),
+        &[home, battery, Color::Rgb(100, 200, 255)],
+    );
+    render_energy_split(
+        f,
+        chunks[2],
+        ("🏠 HOME", home),
+        breakdown.load_total,
+        true,
+        &breakdown.load_flows(),
+        &[solar, Color::Rgb(255, 165, 0), grid],
+    );
+    render_costs(f, app, chunks[3]);
+}
+
+fn render_costs(f: &mut Frame, app: &AppState, area: Rect) {
+    if area.height < 3 {
+        return;
+    }
+
+    let block = Block::default()
+        .title(" COST ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(255, 215, 0))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::TOP)
+        .border_style(Style::default().fg(Color::Rgb(200, 50, 200)));
+
+    let Some(cost) = app.history.last().and_then(|d| d.cost.as_ref()) else {
+        let hint = Paragraph::new("Add a [tariff] section to the config to track costs")
+            .style(Style::default().fg(Color::Rgb(120, 120, 120)))
+            .block(block);
+        f.render_widget(hint, area);
+        return;
+    };
+
+    let money = |value: f64| format!("{}{:.2}", cost.currency, value);
+    let header = Row::new(vec![
+        "",
+        "On/Off-Peak kWh",
+        "Grid Import",
+        "Export Credit",
+        "Savings",
+        "Net Cost",
+    ])
+    .style(Style::default().fg(Color::Rgb(150, 150, 150)));
+
+    let rows = [
+        ("Today", &cost.today),
+        ("This Month", &cost.month),
+        ("This Year", &cost.year),
+    ]
+    .into_iter()
+    .map(|(label, day)| {
+        Row::new(vec![
+            label.to_string(),
+            format!("{:.2} / {:.2}", day.on_peak_kwh, day.off_peak_kwh),
+            money(day.import_cost),
+            money(day.export_credit),
+            money(day.savings()),
+            money(day.net_cost()),
+        ])
+        .style(Style::default().fg(Color::White))
+    });
+
+    let table = Table::new(
+        rows,
+        &[
+            Constraint::Length(12),
+            Constraint::Length(18),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(14),
+        ],
+    )
+    .header(header)
+    .column_spacing(2);
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Min(0)])
+        .split(inner);
+    f.render_widget(table, sections[0]);
+
+    let bill = &cost.bill;
+    let bill_text = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled(
+                format!("Bill estimate since {}: ", bill.cycle_start),
+                Style::default().fg(Color::Rgb(150, 150, 150)),
+            ),
+            Span::styled(
+                format!("{:.1} kWh → {}", bill.kwh, money(bill.total)),
+                Style::default()
+                    .fg(Color::Rgb(255, 215, 0))
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(Span::styled(
+            format!(
+                "Energy {}  +  Ft {}  +  Service {}  +  VAT {}",
+                money(bill.energy_charge),
+                money(bill.ft_charge),
+                money(bill.service_charge),
+                money(bill.vat)
+            ),
+            Style::default().fg(Color::Rgb(120, 120, 120)),
+        )),
+    ]);
+    f.render_widget(bill_text, sections[1]);
+}
+
+/// A stacked bar for one source/sink plus a text Sankey line per flow
+fn render_energy_split(
+    f: &mut Frame,
+    area: Rect,
+    (title, color): (&str, Color),
+    total: f64,
+    incoming: bool, // Flows point into the node instead of out of it
+    flows: &[(&str, f64)],
+    colors: &[Color],
+) {
+    let mut lines = vec![Line::from(vec![
+        Span::styled(
+            format!("{} ", title),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("{:.2} kWh", total),
+            Style::default().fg(Color::White),
+        ),
+    ])];
+
+    // Stacked bar: one segment per flow, proportional to its share
+    let bar_width = area.width as usize;
+    let mut bar = Vec::new();
+    let mut used = 0;
+    for (idx, (&(_, value), &segment_color)) in flows.iter().zip(colors).enumerate() {
+        let width = if idx == flows.len() - 1 && used > 0 {
+            bar_width.saturating_sub(used)
+        } else {
+            (energy::share(value, flows) / 100.0 * bar_width as f64).round() as usize
+        };
+        let width = width.min(bar_width - used);
+        used += width;
+        if width > 0 && value > 0.0 {
+            bar.push(Span::styled(
+                " ".repeat(width),
+                Style::default().bg(segment_color),
+            ));
+        }
+    }
+    if used < bar_width {
+        bar.push(Span::styled(
+            " ".repeat(bar_width - used),
+            Style::default().bg(Color::Rgb(40, 40, 40)),
+        ));
+    }
+    lines.push(Line::from(bar.clone()));
+    lines.push(Line::from(bar));
+    lines.push(Line::from(""));
+
+    for (&(label, value), &flow_color) in flows.iter().zip(colors) {
+        let percent = energy::share(value, flows);
+        let arrow = format!("──{:>5.1}%──▶", percent);
+        let (from, to) = if incoming {
+            (label, title)
+        } else {
+            (title, label)
+        };
+        let style = if value > 0.0 {
+            Style::default().fg(flow_color)
+        } else {
+            Style::default().fg(Color::Rgb(90, 90, 90))
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:>12} ", from), style),
+            Span::styled(arrow, style),
+            Span::styled(format!(" {:<12}", to), style),
+            Span::styled(
+                format!("{:>8.2} kWh", value),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+    }
+
+    f.render_widget(Paragraph::new(lines), area);
+}
+
+fn render_statistics(f: &mut Frame, app: &AppState, area: Rect) {
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(11), Constraint::Min(0)])
+        .split(area);
+
+    render_payback(f, app, sections[0]);
+    render_overnight(f, app, sections[1]);
+}
+
+fn render_payback(f: &mut Frame, app: &AppState, area: Rect) {
+    let block = Block::default()
+        .title(" SOLAR PAYBACK ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(255, 215, 0))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(200, 170, 0)))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let cost = app.history.last().and_then(|d| d.cost.as_ref());
+    let Some((roi, currency)) = cost.and_then(|c| Some((c.roi.as_ref()?, c.currency.as_str())))
+    else {
+        let hint = Paragraph::new("Add [tariff] and [roi] sections to the config to track payback")
+            .style(Style::default().fg(Color::Rgb(120, 120, 120)));
+        f.render_widget(hint, inner);
+        return;
+    };
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .split(inner);
+
+    let ratio = (roi.paid_back_percent / 100.0).clamp(0.0, 1.0);
+    let gauge = Gauge::default()
+        .gauge_style(
+            Style::default()
+                .fg(get_soc_gradient_color(ratio))
+                .bg(Color::Rgb(40, 40, 40)),
+        )
+        .label(Span::styled(
+            format!("{:.1}% paid back", roi.paid_back_percent),
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .ratio(ratio);
+    f.render_widget(gauge, rows[0]);
+
+    let money = |value: f64| format!("{}{:.2}", currency, value);
+    let payback = match &roi.payback_date {
+        _ if roi.paid_back_percent >= 100.0 => "Paid back".to_string(),
+        Some(date) => date.clone(),
+        None => "-".to_string(),
+    };
+    let values = [
+        ("Investment (incl. loan interest)", money(roi.investment)),
+        ("Savings tracked by emon", money(roi.tracked_savings)),
+        (
+            "Savings before tracking (est.)",
+            money(roi.estimated_savings),
+        ),
+        ("Cumulative savings", money(roi.savings)),
+        ("Remaining", money((roi.investment - roi.savings).max(0.0))),
+        ("Average savings per day", money(roi.savings_per_day)),
+        ("Projected payback date", payback),
+    ];
+    let table = Table::new(
+        values.iter().map(|(label, value)| {
+            Row::new(vec![label.to_string(), value.clone()])
+                .style(Style::default().fg(Color::White))
+        }),
+        &[Constraint::Length(34), Constraint::Min(10)],
+    )
+    .column_spacing(2);
+    f.render_widget(table, rows[2]);
+}
+
+fn render_battery_health(f: &mut Frame, app: &AppState, area: Rect) {
+    let block = Block::default()
+        .title(" BATTERY HEALTH ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(100, 255, 100))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(50, 180, 50)))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let Some(health) = app.history.last().and_then(|d| d.battery_health.as_ref()) else {
+        let hint = Paragraph::new("Set battery_capacity_kwh to track battery cycles and health")
+            .style(Style::default().fg(Color::Rgb(120, 120, 120)));
+        f.render_widget(hint, inner);
+        return;
+    };
+
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // State of health gauge
+            Constraint::Length(1),
+            Constraint::Length(4), // Summary
+            Constraint::Length(1),
+            Constraint::Min(0), // Daily trend
+        ])
+        .split(inner);
+
+    let (ratio, label) = match health.state_of_health {
+        Some(soh) => (
+            (soh / 100.0).clamp(0.0, 1.0),
+            format!("State of health {:.1}%", soh),
+        ),
+        None => (
+            0.0,
+            "State of health: waiting for a full charge to float".to_string(),
+        ),
+    };
+    let gauge = Gauge::default()
+        .gauge_style(
+            Style::default()
+                .fg(get_soc_gradient_color(ratio))
+                .bg(Color::Rgb(40, 40, 40)),
+        )
+        .label(Span::styled(
+            label,
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .ratio(ratio);
+    f.render_widget(gauge, sections[0]);
+
+    let percent = |value: Option<f64>| match value {
+        Some(value) => format!("{:.1}%", value),
+        None => "-".to_string(),
+    };
+    let rated = app
+        .config
+        .home_assistant
+        .battery_capacity_kwh
+        .unwrap_or(0.0);
+    let usable = match health.usable_capacity_kwh {
+        Some(kwh) => format!("{:.2} kWh of {:.2} kWh rated", kwh, rated),
+        None => "-".to_string(),
+    };
+    let summary = [
+        (
+            "Equivalent full cycles",
+            format!(
+                "{:.1} over {} day{}",
+                health.cycles,
+                health.tracked_days,
+                if health.tracked_days == 1 { "" } else { "s" }
+            ),
+        ),
+        (
+            "Depth of discharge today",
+            percent(health.depth_of_discharge),
+        ),
+        ("Round-trip efficiency", percent(health.efficiency)),
+        ("Usable capacity (est.)", usable),
+    ];
+    let table = Table::new(
+        summary
+            .iter()
+            .map(|(label, value)| Row::new(vec![label.to_string(), value.clone()])),
+        &[Constraint::Length(34), Constraint::Min(10)],
+    )
+    .style(Style::default().fg(Color::White))
+    .column_spacing(2);
+    f.render_widget(table, sections[2]);
+
+    let header = Row::new(vec![
+        "Date",
+        "Charge",
+        "Discharge",
+        "Cycles",
+        "DoD",
+        "Efficiency",
+        "Capacity",
+    ])
+    .style(Style::default().fg(Color::Rgb(150, 150, 150)));
+    let rows = health.trend.iter().rev().map(|day| {
+        Row::new(vec![
+            day.date.clone(),
+            format!("{:.2} kWh", day.charge_kwh),
+            format!("{:.2} kWh", day.discharge_kwh),
+            format!("{:.2}", day.cycles),
+            percent(day.depth_of_discharge),
+            percent(day.efficiency),
+            day.capacity_kwh
+                .map(|kwh| format!("{:.2} kWh", kwh))
+                .unwrap_or_else(|| "-".to_string()),
+        ])
+        .style(Style::default().fg(Color::White))
+    });
+    let trend = Table::new(
+        rows,
+        &[
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(12),
+            Constraint::Length(12),
+        ],
+    )
+    .header(header)
+    .column_spacing(2);
+    f.render_widget(trend, sections[4]);
+}
+
+fn render_overnight(f: &mut Frame, app: &AppState, area: Rect) {
+    let block = Block::default()
+        .title(" OVERNIGHT BATTERY ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(150, 150, 255))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(100, 100, 200)))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let Some(forecast) = app.history.last().and_then(|d| d.overnight.as_ref()) else {
+        let hint = Paragraph::new(
+            "Set latitude and longitude under [home_assistant] to forecast the battery overnight",
+        )
+        .style(Style::default().fg(Color::Rgb(120, 120, 120)));
+        f.render_widget(hint, inner);
+        return;
+    };
+
+    let kwh = |value: Option<f64>| match value {
+        Some(value) => format!("{:.2} kWh", value),
+        None => "-".to_string(),
+    };
+    let source = match forecast.night_source {
+        Some(NightSource::Sensor) => "night consumption sensor",
+        Some(NightSource::Learned) => "learned from recent nights",
+        None => "learning (needs one full night)",
+    };
+    let (verdict, verdict_color) = match forecast.shortfall_kwh {
+        Some(shortfall) if shortfall > 0.0 => (
+            format!("Short by {:.2} kWh, expect grid import", shortfall),
+            Color::Red,
+        ),
+        Some(_) => (
+            "Battery lasts until sunrise".to_string(),
+            Color::Rgb(100, 255, 100),
+        ),
+        None => ("-".to_string(), Color::White),
+    };
+
+    let rows = vec![
+        Row::new(vec!["Sunset".to_string(), format_clock(&forecast.sunset)]),
+        Row::new(vec!["Sunrise".to_string(), format_clock(&forecast.sunrise)]),
+        Row::new(vec![
+            "Available above reserve".to_string(),
+            kwh(forecast.available_kwh),
+        ]),
+        Row::new(vec![
+            "Night consumption".to_string(),
+            format!("{} ({})", kwh(forecast.night_kwh), source),
+        ]),
+        Row::new(vec![
+            "Needed until sunrise".to_string(),
+            kwh(forecast.needed_kwh),
+        ]),
+        Row::new(vec!["Forecast".to_string(), verdict]).style(
+            Style::default()
+                .fg(verdict_color)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ];
+    let table = Table::new(rows, &[Constraint::Length(34), Constraint::Min(10)])
+        .style(Style::default().fg(Color::White))
+        .column_spacing(2);
+    f.render_widget(table, inner);
+}
+
+fn render_surplus(f: &mut Frame, app: &AppState, area: Rect) {
+    let block = Block::default()
+        .title(" EXCESS SOLAR ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(255, 215, 0))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(200, 170, 0)))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let Some(advice) = app.history.last().and_then(|d| d.surplus.as_ref()) else {
+        return;
+    };
+
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    let (status, status_color) = match (&advice.since, advice.sustained) {
+        (Some(since), true) => (
+            format!(
+                "Surplus of {:.2} kW since {}, run one of these loads",
+                advice.export / 1000.0,
+                format_clock(since)
+            ),
+            Color::Rgb(100, 255, 100),
+        ),
+        (Some(since), false) => (
+            format!(
+                "Exporting {:.2} kW since {}, waiting for it to last",
+                advice.export / 1000.0,
+                format_clock(since)
+            ),
+            Color::Rgb(255, 215, 0),
+        ),
+        (None, _) => (
+            "No surplus (needs steady export with a full battery)".to_string(),
+            Color::Rgb(150, 150, 150),
+        ),
+    };
+    f.render_widget(
+        Paragraph::new(status).style(Style::default().fg(status_color)),
+        sections[0],
+    );
+
+    let header = Row::new(vec!["Load", "Power", "Status"])
+        .style(Style::default().fg(Color::Rgb(150, 150, 150)));
+    let rows = advice.loads.iter().map(|load| {
+        let (status, color) = if load.switched {
+            ("On (switched by emon)", Color::Rgb(100, 200, 255))
+        } else if advice.sustained && load.fits {
+            ("Suggested", Color::Rgb(100, 255, 100))
+        } else if load.fits {
+            ("Fits", Color::White)
+        } else {
+            ("Too large", Color::Rgb(120, 120, 120))
+        };
+        Row::new(vec![
+            load.name.clone(),
+            format!("{:.0} W", load.power),
+            status.to_string(),
+        ])
+        .style(Style::default().fg(color))
+    });
+    let table = Table::new(
+        rows,
+        &[
+            Constraint::Min(20),
+            Constraint::Length(10),
+            Constraint::Length(24),
+        ],
+    )
+    .header(header)
+    .column_spacing(2);
+    f.render_widget(table, sections[1]);
+
+    if let Some(action) = &advice.last_action {
+        f.render_widget(
+            Paragraph::new(action.as_str()).style(Style::default().fg(Color::Rgb(150, 150, 150))),
+            sections[2],
+        );
+    }
+}
+
+/// Footer hint while a sustained surplus could run a configured load
+fn surplus_hint(app: &AppState) -> Option<String> {
+    let advice = app.history.last()?.surplus.as_ref()?;
+    if !advice.sustained {
+        return None;
+    }
+    let suggested: Vec<&str> = advice
+        .loads
+        .iter()
+        .filter(|l| l.fits && !l.switched)
+        .map(|l| l.name.as_str())
+        .collect();
+    if suggested.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "☀ {:.2} kW excess solar, run: {}",
+        advice.export / 1000.0,
+        suggested.join(", ")
+    ))
+}
+
+/// Footer warning when the battery is not expected to last until sunrise
+fn overnight_warning(app: &AppState) -> Option<String> {
+    let forecast = app.history.last()?.overnight.as_ref()?;
+    let shortfall = forecast.shortfall_kwh.filter(|kwh| *kwh > 0.0)?;
+    Some(format!(
+        "⚠ Battery runs short by {:.2} kWh before sunrise ({}), expect {:.2} kWh grid import",
+        shortfall,
+        format_clock(&forecast.sunrise),
+        shortfall
+    ))
+}
+
+/// "HH:MM" from an RFC 3339 timestamp
+fn format_clock(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.format("%H:%M").to_string())
+        .unwrap_or_else(|_| "-".to_string())
+}
+
+fn solar_val(latest: Option<&crate::state::PowerData>) -> f64 {
+    latest.map(|d| d.solar).unwrap_or(0.0)
+}
+
+fn load_val(latest: Option<&crate::state::PowerData>) -> f64 {
+    latest.map(|d| d.load).unwrap_or(0.0)
+}
+
+fn battery_soc(latest: Option<&crate::state::PowerData>) -> f64 {
+    latest.map(|d| d.battery_soc).unwrap_or(0.0)
+}
+
+/// SoC text when neither a `battery_soc` entity nor the estimator provides a value, which would
+/// otherwise read 0%: "estimating…" while the estimator (enabled by `battery_capacity_kwh`) has no
+/// starting point yet, "—" without any SoC source
+fn missing_soc(app: &AppState, latest: Option<&crate::state::PowerData>) -> Option<&'static str> {
+    let ha = &app.config.home_assistant;
+    if ha.entities.battery_soc.is_some() || latest.is_some_and(|d| d.battery_soc_estimated) {
+        return None;
+    }
+    Some(if ha.battery_capacity_kwh.is_some() {
+        "estimating…"
+    } else {
+        UNAVAILABLE
+    })
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Normal => Color::White,
+        Severity::Warning => Color::Rgb(255, 165, 0), // Orange
+        Severity::Critical => Color::Red,
+    }
+}
+
+/// Shown instead of a value whose sensor is unavailable, unknown or could not be fetched
+const UNAVAILABLE: &str = "—";
+
+/// Color for unavailable readings
+const UNAVAILABLE_COLOR: Color = Color::Rgb(255, 165, 0); // Orange
+
+/// True when the latest reading has no usable value for the metric
+fn is_unavailable(latest: Option<&crate::state::PowerData>, metric: &str) -> bool {
+    latest.is_some_and(|d| !d.is_available(metric))
+}
+
+/// `text`, or "—" in the warning color when the metric is unavailable
+fn metric_span(
+    latest: Option<&crate::state::PowerData>,
+    metric: &str,
+    text: String,
+) -> Span<'static> {
+    if is_unavailable(latest, metric) {
+        Span::styled(UNAVAILABLE, Style::default().fg(UNAVAILABLE_COLOR))
+    } else {
+        Span::raw(text)
+    }
+}
+
+/// `text`, or "—" when the metric is unavailable
+fn or_unavailable(latest: Option<&crate::state::PowerData>, metric: &str, text: String) -> String {
+    if is_unavailable(latest, metric) {
+        UNAVAILABLE.to_string()
+    } else {
+        text
+    }
+}
+
+fn get_power_trend<F>(
+    history: &[crate::state::PowerData],
+    metric: &str,
+    get_value: F,
+) -> &'static str
+where
+    F: Fn(&crate::state::PowerData) -> f64,
+{
+    if history.len() < 2 {
         return "—";
     }
 
-    let current = get_value(&history[history.len() - 1]);
-    let previous = get_value(&history[history.len() - 2]);
+    let (latest, before) = (&history[history.len() - 1], &history[history.len() - 2]);
+    if !latest.is_available(metric) || !before.is_available(metric) {
+        return "—";
+    }
+    let current = get_value(latest);
+    let previous = get_value(before);
 
     if current > previous * 1.05 {
         "↑ Rising"
@@ -1203,15 +2675,37 @@ where
     }
 }
 
+/// "full in 1h 42m" while charging, "empty at 03:15" while discharging towards the reserve SoC
+fn battery_eta(app: &AppState, data: &crate::state::PowerData) -> Option<String> {
+    if let Some(seconds) = data.battery_time_to_full {
+        return Some(format!("full in {}", format_duration(seconds)));
+    }
+
+    let seconds = data.battery_time_to_reserve?;
+    if seconds >= 86400.0 {
+        return Some(format!("empty in {}", format_duration(seconds)));
+    }
+    let at = chrono::Utc::now().with_timezone(&app.config.home_assistant.get_timezone())
+        + chrono::Duration::seconds(seconds as i64);
+    Some(format!("empty at {}", at.format("%H:%M")))
+}
+
+/// Compact duration, e.g. "45m", "1h 42m", "2d 3h"
+fn format_duration(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as u64;
+    match (minutes / 1440, minutes / 60 % 24, minutes % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
 fn format_battery_power(power: f64, is_floating: bool) -> String {
-    if power > 10.0 {
-        format!("🔌 {:.2} kW (Charging)", power / 1000.0)
-    } else if power < -10.0 {
-        format!("⚡ {:.2} kW (Discharging)", (-power) / 1000.0)
-    } else if is_floating {
-        "Idle (Floating)".to_string()
-    } else {
-        "Idle".to_string()
+    match BatteryState::from_power(power, is_floating) {
+        BatteryState::Charging => format!("🔌 {:.2} kW (Charging)", power / 1000.0),
+        BatteryState::Discharging => format!("⚡ {:.2} kW (Discharging)", (-power) / 1000.0),
+        BatteryState::Floating => "Idle (Floating)".to_string(),
+        BatteryState::Idle => "Idle".to_string(),
     }
 }
 
@@ -1338,6 +2832,294 @@ fn apply_gradient(base_color: Color, position: f64) -> Color {
     }
 }
 
+fn render_action_menu(f: &mut Frame, app: &AppState, area: Rect) {
+    let Some(menu) = app.action_menu else {
+        return;
+    };
+    let actions = &app.config.actions;
+
+    let popup_width = (area.width as f32 * 0.6).min(80.0) as u16;
+    let popup_height = (actions.len() as u16 + 6).min(area.height);
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(" ACTIONS ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(100, 200, 255))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(100, 200, 255)))
+        .style(Style::default().bg(Color::Rgb(10, 20, 30)))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    let rows = actions.iter().map(|action| {
+        Row::new(vec![
+            Span::styled(action.name.clone(), Style::default().fg(Color::White)),
+            Span::styled(
+                action.service.clone(),
+                Style::default().fg(Color::Rgb(150, 150, 150)),
+            ),
+        ])
+    });
+    let table = Table::new(rows, &[Constraint::Min(20), Constraint::Min(16)])
+        .highlight_style(Style::default().bg(Color::Rgb(50, 50, 70)))
+        .highlight_symbol("▶ ")
+        .column_spacing(2);
+    let mut table_state = TableState::default();
+    table_state.select(Some(menu.selected));
+    f.render_stateful_widget(table, sections[0], &mut table_state);
+
+    let prompt = if menu.confirming {
+        Paragraph::new(Span::styled(
+            format!(
+                "Run \"{}\"?  Enter/y confirm  ·  any other key cancels",
+                actions[menu.selected].name
+            ),
+            Style::default()
+                .fg(Color::Rgb(255, 215, 0))
+                .add_modifier(Modifier::BOLD),
+        ))
+    } else {
+        Paragraph::new("↑/↓ select  ·  Enter run  ·  Esc close")
+            .style(Style::default().fg(Color::Rgb(150, 150, 150)))
+    };
+    f.render_widget(prompt, sections[2]);
+}
+
+fn render_inspector(f: &mut Frame, app: &AppState, area: Rect) {
+    let Some(inspector) = &app.inspector else {
+        return;
+    };
+    let popup_area = Rect {
+        x: area.width / 20,
+        y: area.height / 20,
+        width: area.width - area.width / 10,
+        height: area.height - area.height / 10,
+    };
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(" INSPECTOR ")
+        .title_style(
+            Style::default()
+                .fg(Color::Rgb(100, 200, 255))
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Rgb(100, 200, 255)))
+        .style(Style::default().bg(Color::Rgb(10, 20, 30)))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    // Tabs and search box
+    let rows = inspector.rows(&app.config.home_assistant.entities, &app.ha);
+    let tab_style = |tab: InspectorTab| {
+        if inspector.tab == tab {
+            Style::default()
+                .fg(Color::Rgb(255, 215, 0))
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        } else {
+            Style::default().fg(Color::Rgb(150, 150, 150))
+        }
+    };
+    let search_style = if inspector.searching {
+        Style::default()
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Rgb(150, 150, 150))
+    };
+    let cursor = if inspector.searching { "▏" } else { "" };
+    let header = Line::from(vec![
+        Span::styled("Configured entities", tab_style(InspectorTab::Configured)),
+        Span::raw("   "),
+        Span::styled("All entities", tab_style(InspectorTab::All)),
+        Span::styled(
+            format!("   {} shown   ", rows.len()),
+            Style::default().fg(Color::Rgb(120, 120, 120)),
+        ),
+        Span::styled(format!("/ {}{}", inspector.query, cursor), search_style),
+    ]);
+    f.render_widget(Paragraph::new(header), sections[0]);
+
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(sections[1]);
+
+    let message = match (&inspector.tab, &inspector.all) {
+        (InspectorTab::All, None) => Some("Loading /api/states...".to_string()),
+        (InspectorTab::All, Some(Err(e))) => Some(e.clone()),
+        _ if rows.is_empty() => Some("No matching entities".to_string()),
+        _ => None,
+    };
+    if let Some(message) = message {
+        f.render_widget(
+            Paragraph::new(message).style(Style::default().fg(Color::Rgb(150, 150, 150))),
+            body[0],
+        );
+    } else {
+        let list = rows.iter().map(|row| {
+            let (state, color) = match (&row.state, &row.error) {
+                (Some(state), _) => (state.state.clone(), Color::White),
+                (None, Some(_)) => ("fetch failed".to_string(), Color::Red),
+                (None, None) if app.attached_to.is_some() => {
+                    ("via collector".to_string(), Color::Rgb(120, 120, 120))
+                }
+                (None, None) => ("not fetched".to_string(), Color::Rgb(120, 120, 120)),
+            };
+            let name = row.setting.unwrap_or(row.entity_id).to_string();
+            Row::new(vec![name, state]).style(Style::default().fg(color))
+        });
+        let table = Table::new(
+            list,
+            &[Constraint::Percentage(60), Constraint::Percentage(40)],
+        )
+        .highlight_style(Style::default().bg(Color::Rgb(50, 50, 70)))
+        .highlight_symbol("▶ ")
+        .column_spacing(1);
+        let mut table_state = TableState::default();
+        table_state.select(Some(inspector.selected.min(rows.len() - 1)));
+        f.render_stateful_widget(table, body[0], &mut table_state);
+    }
+
+    if let Some(row) = rows.get(inspector.selected.min(rows.len().saturating_sub(1))) {
+        let details = Paragraph::new(inspector_details(app, row))
+            .block(
+                Block::default()
+                    .borders(Borders::LEFT)
+                    .border_style(Style::default().fg(Color::Rgb(60, 60, 80)))
+                    .padding(Padding::horizontal(1)),
+            )
+            .scroll((inspector.scroll, 0));
+        f.render_widget(details, body[1]);
+    }
+
+    let keys = if inspector.searching {
+        "Type to search  ·  Enter/Esc done"
+    } else {
+        "↑/↓ select  ·  / search  ·  Tab switch list  ·  J/K scroll attributes  ·  r reload all  ·  Esc close"
+    };
+    f.render_widget(
+        Paragraph::new(keys).style(Style::default().fg(Color::Rgb(150, 150, 150))),
+        sections[2],
+    );
+}
+
+/// Raw state, timestamps, latency and attributes of one entity
+fn inspector_details(app: &AppState, row: &InspectorRow) -> Vec<Line<'static>> {
+    let label = |text: &str| {
+        Span::styled(
+            format!("{:<14}", text),
+            Style::default().fg(Color::Rgb(150, 150, 150)),
+        )
+    };
+    let tz = app.config.home_assistant.get_timezone();
+    let timestamp = |value: Option<&String>| {
+        value
+            .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
+            .map(|t| t.with_timezone(&tz).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    let mut lines = vec![Line::from(vec![
+        label("Entity"),
+        Span::raw(row.entity_id.to_string()),
+    ])];
+    if let Some(setting) = row.setting {
+        lines.push(Line::from(vec![label("Setting"), Span::raw(setting)]));
+    }
+    if let Some(error) = &row.error {
+        lines.push(Line::from(vec![
+            label("Error"),
+            Span::styled(error.clone(), Style::default().fg(Color::Red)),
+        ]));
+    }
+    if let (Some(latency), Some(at)) = (row.latency, row.fetched_at) {
+        lines.push(Line::from(vec![
+            label("Latency"),
+            Span::raw(format!(
+                "{} ms, fetched {}s ago",
+                latency.as_millis(),
+                at.elapsed().as_secs()
+            )),
+        ]));
+    }
+    let Some(state) = &row.state else {
+        // Attached clients render the collector's readings and never fetch entities themselves
+        if let (Some(addr), None) = (&app.attached_to, &row.error) {
+            let note = Style::default().fg(Color::Rgb(150, 150, 150));
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!("Fetched by the collector at {}.", addr),
+                note,
+            )));
+            lines.push(Line::from(Span::styled(
+                "Attached clients do not receive raw states;",
+                note,
+            )));
+            lines.push(Line::from(Span::styled(
+                "the All entities tab queries Home Assistant.",
+                note,
+            )));
+        }
+        return lines;
+    };
+    lines.push(Line::from(vec![
+        label("State"),
+        Span::styled(
+            format!("{:?}", state.state),
+            Style::default()
+                .fg(Color::Rgb(255, 215, 0))
+                .add_modifier(Modifier::BOLD),
+        ),
+    ]));
+    lines.push(Line::from(vec![
+        label("Last changed"),
+        Span::raw(timestamp(state.last_changed.as_ref())),
+    ]));
+    lines.push(Line::from(vec![
+        label("Last updated"),
+        Span::raw(timestamp(state.last_updated.as_ref())),
+    ]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(label("Attributes")));
+    let attributes = serde_json::to_string_pretty(&state.attributes).unwrap_or_default();
+    lines.extend(attributes.lines().map(|line| Line::from(line.to_string())));
+    lines
+}
+
 fn render_error_popup(f: &mut Frame, app: &AppState, area: Rect) {
     if let Some(error) = &app.error {
         // Calculate popup size (centered, 60% width, auto height)
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use crate::config::Config;
+use crate::feed::Frame;
+use crate::http;
+use crate::snapshot::Snapshot;
+
+/// Self-contained dashboard page (no external scripts, styles or fonts)
+const DASHBOARD_HTML: &str = include_str!("assets/dashboard.html");
+
+/// Display settings the page needs to scale gauges and charts like the TUI
+#[derive(Debug, Clone, Serialize)]
+struct Settings {
+    max_solar_power: f64,
+    max_daily_energy: f64,
+    battery_capacity_kwh: Option<f64>,
+    history_seconds: usize,
+    history_display: String,
+}
+
+/// Full state for the initial page load
+#[derive(Serialize)]
+struct StatePayload<'a> {
+    settings: &'a Settings,
+    #[serde(flatten)]
+    update: UpdatePayload<'a>,
+    /// (solar, load, battery_power) in Watts, oldest first; null while unavailable
+    history: Vec<[Option<f64>; 3]>,
+}
+
+/// Incremental update pushed over SSE after every fetch
+#[derive(Serialize)]
+struct UpdatePayload<'a> {
+    up: bool,
+    error: Option<&'a str>,
+    snapshot: Option<Snapshot<'a>>,
+}
+
+fn update_payload(frame: &Frame, tz: Tz) -> UpdatePayload<'_> {
+    UpdatePayload {
+        up: frame.up,
+        error: frame.error.as_deref(),
+        snapshot: frame
+            .latest()
+            .map(|data| Snapshot::new(data, frame.battery_floating, tz)),
+    }
+}
+
+/// Bind the dashboard port and serve the page, `/api/state` and `/api/events`
+pub async fn start(config: &Config, frames: watch::Receiver<Arc<Frame>>) -> Result<()> {
+    let Some(web) = config.web.as_ref().filter(|w| w.enabled) else {
+        return Ok(());
+    };
+
+    let listener = TcpListener::bind(&web.listen)
+        .await
+        .with_context(|| format!("Failed to bind web dashboard on {}", web.listen))?;
+
+    let ha = &config.home_assistant;
+    let settings = Arc::new(Settings {
+        max_solar_power: ha.max_solar_power.unwrap_or(18000.0),
+        max_daily_energy: ha.max_daily_energy.unwrap_or(100.0),
+        battery_capacity_kwh: ha.battery_capacity_kwh,
+        history_seconds: ha.get_history_seconds(),
+        history_display: ha.get_history_duration_display(),
+    });
+    let tz = ha.get_timezone();
+
+    tokio::spawn(async move {
+        loop {
+            let stream = http::accept(&listener, "web dashboard").await;
+            tokio::spawn(handle(stream, frames.clone(), settings.clone(), tz));
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    mut frames: watch::Receiver<Arc<Frame>>,
+    settings: Arc<Settings>,
+    tz: Tz,
+) -> Result<()> {
+    let request = http::read_request(&mut stream).await?;
+    if request.method != "GET" {
+        return http::write_response(&mut stream, "405 Method Not Allowed", "text/plain", b"")
+            .await;
+    }
+
+    match request.path.as_str() {
+        "/" | "/index.html" => {
+            http::write_response(
+                &mut stream,
+                "200 OK",
+                "text/html; charset=utf-8",
+                DASHBOARD_HTML.as_bytes(),
+            )
+            .await
+        }
+        "/api/state" => {
+            let frame = frames.borrow().clone();
+            let payload = StatePayload {
+                settings: &settings,
+                update: update_payload(&frame, tz),
+                history: frame
+                    .history
+                    .iter()
+                    .map(|d| {
+                        let value = |metric, value| d.is_available(metric).then_some(value);
+                        [
+                            value("solar", d.solar),
+                            value("load", d.load),
+                            value("battery_power", d.battery_power),
+                        ]
+                    })
+                    .collect(),
+            };
+            let body = serde_json::to_vec(&payload)?;
+            http::write_response(&mut stream, "200 OK", "application/json", &body).await
+        }
+        "/api/events" => {
+            http::write_stream_head(&mut stream, "text/event-stream").await?;
+            frames.borrow_and_update();
+            while frames.changed().await.is_ok() {
+                let frame = frames.borrow_and_update().clone();
+                let json = serde_json::to_string(&update_payload(&frame, tz))?;
+                stream
+                    .write_all(format!("data: {}\n\n", json).as_bytes())
+                    .await?;
+                stream.flush().await?;
+            }
+            Ok(())
+        }
+        _ => http::write_response(&mut stream, "404 Not Found", "text/plain", b"").await,
+    }
+}